@@ -1,18 +1,48 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, collections::BTreeMap};
+
+use log::{debug, error, info, trace};
+
+use serde::{Deserialize, Serialize};
 
 use self::{
-    board::GameBoard,
+    board::{generator::base_board_generator::generate_board, DoubleDiceRoll, GameBoard},
+    event::GameEvent,
+    piece::PieceInventory,
     player::{Player, PlayerId},
-    state::state_machine::StateMachine,
+    replay::RecordedAction,
+    score::PlayerScore,
+    state::{
+        action_data::GameActionKind,
+        game_rules::GameRules,
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, StateMachine},
+        states::game_over::GameOver,
+    },
+    title::BonusTitle,
     trade::TradeOffer,
 };
 
 pub mod board;
 pub mod color;
+pub mod event;
+pub mod piece;
 pub mod player;
+pub mod random;
+pub mod replay;
+pub mod save;
+pub mod score;
+pub mod snapshot;
 pub mod state;
+#[cfg(test)]
+mod test_util;
+pub mod title;
 pub mod trade;
+pub mod transaction;
 
+pub const MIN_PLAYER_COUNT: usize = 2;
+pub const MAX_PLAYER_COUNT: usize = color::SEAT_COLORS.len();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameError {
     ActionFailed,
     ActionDataInvalid,
@@ -20,11 +50,34 @@ pub enum GameError {
     NotPlayerTurn,
     InvalidLocation,
     NotEnoughResources,
+    NoDevelopmentCardsLeft,
+    DevelopmentCardNotOwned,
+    DevelopmentCardNotPlayable,
+    DevelopmentCardBoughtThisTurn,
+    DevelopmentCardAlreadyPlayedThisTurn,
+    NoMatchingTradeContract,
+    NotEnoughBankResources,
+    NoPiecesLeft,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameSettings {
     pub players: u8,
+    pub victory_points_to_win: usize,
+    pub pieces: PieceInventory,
+    /// Drives board generation, deck shuffle, dice and robbery
+    pub seed: u64,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            players: 4,
+            victory_points_to_win: 10,
+            pieces: PieceInventory::default(),
+            seed: rand::random(),
+        }
+    }
 }
 
 pub struct Game {
@@ -32,8 +85,13 @@ pub struct Game {
     settings: GameSettings,
     state_machine: RefCell<StateMachine>,
     current_player_index: usize,
+    turn: usize,
+    dice_roll: Option<DoubleDiceRoll>,
     players: Vec<RefCell<Player>>,
     active_trade_offer: RefCell<Option<TradeOffer>>,
+    title_holders: BTreeMap<BonusTitle, PlayerId>,
+    events: Vec<GameEvent>,
+    recorded_actions: Vec<RecordedAction>,
 }
 
 impl Game {
@@ -43,8 +101,142 @@ impl Game {
             settings: settings,
             state_machine: RefCell::new(state_machine),
             current_player_index: 0,
+            turn: 0,
+            dice_roll: None,
             players: vec![],
             active_trade_offer: RefCell::new(None),
+            title_holders: BTreeMap::new(),
+            events: vec![],
+            recorded_actions: vec![],
+        }
+    }
+
+    /// Creates a game with a board generated from the seed of the settings and the base game rules
+    pub fn generate(settings: GameSettings, players: Vec<Player>) -> Result<Self, String> {
+        Game::generate_with_rules(settings, players, GameRules::base())
+    }
+
+    pub fn generate_with_rules(
+        settings: GameSettings,
+        players: Vec<Player>,
+        rules: GameRules,
+    ) -> Result<Self, String> {
+        if players.len() < MIN_PLAYER_COUNT || players.len() > MAX_PLAYER_COUNT {
+            return Err(format!(
+                "Player count {} is not between {} and {}",
+                players.len(),
+                MIN_PLAYER_COUNT,
+                MAX_PLAYER_COUNT
+            ));
+        }
+        if players
+            .iter()
+            .enumerate()
+            .any(|(seat, player)| *player.get_id() != seat)
+        {
+            return Err("Player ids have to match the seat order".to_string());
+        }
+        let board = generate_board(settings.seed)?;
+        let state_machine = rules.build()?;
+
+        let mut game = Game::from(board, settings, state_machine);
+        game.players = players.into_iter().map(RefCell::new).collect();
+        Ok(game)
+    }
+
+    pub fn get_settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    /// Runs the action in the current state and ends the game
+    /// if the current player reached the victory points afterwards.
+    /// Failed actions add no events and are only recorded if they advanced a random stream
+    pub fn perform_action(
+        &mut self,
+        player_id: &PlayerId,
+        action: GameActionKind,
+    ) -> GameActionResult {
+        let recorded_action = action.clone();
+        let event_count = self.events.len();
+        let random_stream_positions = self.board.get_random_stream_positions();
+        let action_result = self.run_action(player_id, action);
+
+        if action_result.is_err() {
+            self.events.truncate(event_count);
+            if self.board.get_random_stream_positions() == random_stream_positions {
+                return action_result;
+            }
+        }
+        self.recorded_actions.push(RecordedAction {
+            player_id: *player_id,
+            action: recorded_action,
+            error: action_result.err(),
+        });
+        action_result
+    }
+
+    fn run_action(&mut self, player_id: &PlayerId, action: GameActionKind) -> GameActionResult {
+        if self.players.get(*player_id).is_none() {
+            trace!("Player {} not found", player_id);
+            return Err(GameError::ActionNotAllowed);
+        }
+
+        let mut state = match self.state_machine.borrow_mut().take_current_state() {
+            None => {
+                error!("Current state is not registered");
+                return Err(GameError::ActionFailed);
+            }
+            Some(state) => state,
+        };
+
+        let action_result = state.perform_action(self, player_id, action);
+        self.state_machine.borrow_mut().restore_state(self, state);
+
+        if action_result.is_ok() {
+            self.check_victory();
+        }
+        action_result
+    }
+
+    /// Actions the player can perform in the current state
+    pub fn get_legal_actions(&self, player_id: &PlayerId) -> Vec<LegalAction> {
+        if self.players.get(*player_id).is_none() {
+            trace!("Player {} not found", player_id);
+            return vec![];
+        }
+        self.state_machine
+            .borrow()
+            .get_legal_actions(self, player_id)
+    }
+
+    fn check_victory(&mut self) {
+        if self.players.is_empty()
+            || self.state_machine.borrow().get_current_state_id() == GameOver::get_id()
+        {
+            return;
+        }
+
+        let player_id = *self.get_current_player().borrow().get_id();
+        let score = PlayerScore::from(self, &player_id);
+        if score.get_total() < self.settings.victory_points_to_win {
+            return;
+        }
+
+        info!(
+            "Player {} won with {} victory points",
+            player_id,
+            score.get_total()
+        );
+        self.add_event(GameEvent::GameWon {
+            player_id,
+            victory_points: score.get_total(),
+        });
+        if let Err(err) = self
+            .state_machine
+            .borrow_mut()
+            .transition_to(self, GameOver::get_id())
+        {
+            error!("Failed to end game \"{}\"", err);
         }
     }
 
@@ -65,7 +257,61 @@ impl Game {
     }
 
     pub fn end_turn(&mut self) {
+        self.add_turn_ended_event();
         self.current_player_index = (self.current_player_index + 1) % self.players.len();
+        self.turn += 1;
+        self.dice_roll = None;
+    }
+
+    /// Passes the turn to the previous player as in the reverse setup round
+    pub fn end_turn_reverse(&mut self) {
+        self.add_turn_ended_event();
+        self.current_player_index =
+            (self.current_player_index + self.players.len() - 1) % self.players.len();
+        self.turn += 1;
+        self.dice_roll = None;
+    }
+
+    fn add_turn_ended_event(&mut self) {
+        let player_id = *self.get_current_player().borrow().get_id();
+        self.add_event(GameEvent::TurnEnded {
+            player_id,
+            turn: self.turn,
+        });
+    }
+
+    /// Every action performed since the game started, used to create replays
+    pub fn get_recorded_actions(&self) -> &Vec<RecordedAction> {
+        &self.recorded_actions
+    }
+
+    /// Appends the event to the ordered event log
+    pub fn add_event(&mut self, event: GameEvent) {
+        debug!("Game event {:?}", event);
+        self.events.push(event);
+    }
+
+    /// Every event since the game started in the order they happened
+    pub fn get_events(&self) -> &Vec<GameEvent> {
+        &self.events
+    }
+
+    /// Events after the first `event_count` events, e.g. the events of the last action
+    pub fn get_events_since(&self, event_count: usize) -> &[GameEvent] {
+        &self.events[event_count.min(self.events.len())..]
+    }
+
+    /// Number of turns which were ended since the game started
+    pub fn get_turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn get_dice_roll(&self) -> &Option<DoubleDiceRoll> {
+        &self.dice_roll
+    }
+
+    pub fn set_dice_roll(&mut self, dice_roll: DoubleDiceRoll) {
+        self.dice_roll = Some(dice_roll);
     }
 
     pub fn is_player_turn(&self, player_id: &PlayerId) -> bool {
@@ -103,4 +349,23 @@ impl Game {
     pub fn complete_trade_offer(&self) {
         self.active_trade_offer.borrow_mut().take();
     }
+
+    pub fn get_title_holder(&self, title: &BonusTitle) -> Option<&PlayerId> {
+        self.title_holders.get(title)
+    }
+
+    pub fn get_player_titles(&self, player_id: &PlayerId) -> Vec<BonusTitle> {
+        self.title_holders
+            .iter()
+            .filter(|(_, holder_id)| *holder_id == player_id)
+            .map(|(title, _)| *title)
+            .collect()
+    }
+
+    pub fn set_title_holder(&mut self, title: BonusTitle, player_id: Option<PlayerId>) {
+        match player_id {
+            None => self.title_holders.remove(&title),
+            Some(player_id) => self.title_holders.insert(title, player_id),
+        };
+    }
 }
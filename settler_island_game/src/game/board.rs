@@ -1,25 +1,35 @@
 use std::{
     any,
     cell::RefCell,
+    collections::BTreeMap,
     sync::{Arc, Mutex},
 };
 
 use rand::{
     distributions::{Distribution, Standard},
-    rngs::StdRng,
     Rng, SeedableRng,
 };
+use serde::{Deserialize, Serialize};
 
 use self::{
-    hexagon::{hexagon_map::HexagonMap, hexagon_tile::HexagonTile},
+    hexagon::{
+        cube_coordinates::CubeCoordinates, hexagon_map::HexagonMap, hexagon_tile::HexagonTile,
+    },
     location::{
-        dice_chip_location::DiceChipLocation, robber_location::RobberLocation,
+        dice_chip_location::DiceChipLocation,
+        road_location::{PlayerRoad, RoadLocationId},
+        robber_location::RobberLocation,
+        settlement_location::{PlayerSettlement, SettlementLocationId, SettlementType},
         settlement_map::SettlementMap,
     },
-    resource::base_resource::ResourcedId,
+    resource::{bank::Bank, base_resource::Resource, player_resources::ResourceCollection},
 };
 
-use super::state::states::development_card::DevelopmentCard;
+use super::{
+    player::PlayerId,
+    random::{GameRng, RandomStream},
+    state::states::development_card::DevelopmentCard,
+};
 
 pub mod generator;
 pub mod hexagon;
@@ -27,6 +37,7 @@ pub mod location;
 pub mod resource;
 pub mod trade_contract;
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct DoubleDiceRoll {
     dice_a: u8,
     dice_b: u8,
@@ -56,32 +67,53 @@ impl Distribution<DoubleDiceRoll> for Standard {
 }
 
 pub struct GameBoard {
-    rng: StdRng,
+    dice_rng: GameRng,
+    robbery_rng: GameRng,
     tile_map: HexagonMap,
     settlement_map: SettlementMap,
     dice_chips: Vec<DiceChipLocation>,
     robber: RobberLocation,
-    board_resources: Vec<ResourcedId>,
+    board_resources: Vec<Resource>,
+    bank: Bank,
+    development_cards: Vec<DevelopmentCard>,
+}
+
+/// Parts of the board created by a board generator, the random streams are created from the seed
+pub struct GeneratedBoard {
+    pub tile_map: HexagonMap,
+    pub settlement_map: SettlementMap,
+    pub dice_chips: Vec<DiceChipLocation>,
+    pub robber_location: RobberLocation,
+    pub board_resources: Vec<Resource>,
+    pub bank: Bank,
+    pub development_cards: Vec<DevelopmentCard>,
+}
+
+/// Parts of the board which change during the game.
+/// Tiles, dice chips and seaports are generated again from the game seed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedBoard {
+    settlements: BTreeMap<SettlementLocationId, PlayerSettlement>,
+    roads: BTreeMap<RoadLocationId, PlayerId>,
+    robber: RobberLocation,
+    bank: ResourceCollection,
     development_cards: Vec<DevelopmentCard>,
+    dice_rng: GameRng,
+    robbery_rng: GameRng,
 }
 
 impl GameBoard {
-    pub fn from(
-        tile_map: HexagonMap,
-        settlement_map: SettlementMap,
-        dice_chips: Vec<DiceChipLocation>,
-        robber_location: RobberLocation,
-        board_resources: Vec<ResourcedId>,
-        development_cards: Vec<DevelopmentCard>,
-    ) -> Self {
+    pub fn from(generated_board: GeneratedBoard, seed: u64) -> Self {
         GameBoard {
-            rng: StdRng::seed_from_u64(0),
-            tile_map: tile_map,
-            settlement_map: settlement_map,
-            dice_chips: dice_chips,
-            robber: robber_location,
-            board_resources: board_resources,
-            development_cards: development_cards,
+            dice_rng: RandomStream::Dice.create_rng(seed),
+            robbery_rng: RandomStream::Robbery.create_rng(seed),
+            tile_map: generated_board.tile_map,
+            settlement_map: generated_board.settlement_map,
+            dice_chips: generated_board.dice_chips,
+            robber: generated_board.robber_location,
+            board_resources: generated_board.board_resources,
+            bank: generated_board.bank,
+            development_cards: generated_board.development_cards,
         }
     }
 
@@ -98,7 +130,23 @@ impl GameBoard {
     }
 
     pub fn roll_dice(&mut self) -> DoubleDiceRoll {
-        self.rng.gen()
+        self.dice_rng.gen()
+    }
+
+    pub fn get_robbery_rng_mut(&mut self) -> &mut GameRng {
+        &mut self.robbery_rng
+    }
+
+    /// Positions of the dice and robbery streams, they change whenever a number is drawn
+    pub fn get_random_stream_positions(&self) -> (u128, u128) {
+        (
+            self.dice_rng.get_word_pos(),
+            self.robbery_rng.get_word_pos(),
+        )
+    }
+
+    pub fn get_dice_chips(&self) -> &Vec<DiceChipLocation> {
+        &self.dice_chips
     }
 
     pub fn get_dice_chips_by_number(&self, number: &u8) -> Vec<&DiceChipLocation> {
@@ -119,6 +167,18 @@ impl GameBoard {
         &self.robber
     }
 
+    pub fn move_robber(&mut self, tile: CubeCoordinates) {
+        self.robber = RobberLocation::from(tile);
+    }
+
+    pub fn get_bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    pub fn get_bank_mut(&mut self) -> &mut Bank {
+        &mut self.bank
+    }
+
     pub fn get_development_cards_left(&self) -> usize {
         self.development_cards.len()
     }
@@ -126,4 +186,63 @@ impl GameBoard {
     pub fn draw_development_card(&mut self) -> Option<DevelopmentCard> {
         self.development_cards.pop()
     }
+
+    pub fn get_saved_board(&self) -> SavedBoard {
+        SavedBoard {
+            settlements: self
+                .settlement_map
+                .get_settlements()
+                .into_iter()
+                .filter_map(|location| {
+                    location
+                        .get_settlement()
+                        .as_ref()
+                        .map(|settlement| (location.get_id().clone(), settlement.clone()))
+                })
+                .collect(),
+            roads: self
+                .settlement_map
+                .get_roads()
+                .into_iter()
+                .filter_map(|road| {
+                    road.get_player_road()
+                        .as_ref()
+                        .map(|player_road| (road.get_id().clone(), *player_road.get_player_id()))
+                })
+                .collect(),
+            robber: self.robber,
+            bank: self.bank.get_resources().get_resources(),
+            development_cards: self.development_cards.clone(),
+            dice_rng: self.dice_rng.clone(),
+            robbery_rng: self.robbery_rng.clone(),
+        }
+    }
+
+    /// Applies the saved changes to a freshly generated board
+    pub fn restore_saved_board(&mut self, saved_board: SavedBoard) -> Result<(), String> {
+        for (settlement_id, settlement) in saved_board.settlements {
+            let location = match self.settlement_map.get_settlement_mut(&settlement_id) {
+                None => return Err(format!("Settlement \"{}\" not found", settlement_id)),
+                Some(location) => location,
+            };
+            location.build_settlement(SettlementType::Village, settlement.get_player_id())?;
+            if let SettlementType::City = settlement.get_settlement_type() {
+                location.build_settlement(SettlementType::City, settlement.get_player_id())?;
+            }
+        }
+
+        for (road_id, player_id) in saved_board.roads {
+            match self.settlement_map.get_road_mut(&road_id) {
+                None => return Err(format!("Road \"{}\" not found", road_id)),
+                Some(road) => road.build_road(PlayerRoad::from(player_id))?,
+            }
+        }
+
+        self.robber = saved_board.robber;
+        self.bank = Bank::from_resources(saved_board.bank);
+        self.development_cards = saved_board.development_cards;
+        self.dice_rng = saved_board.dice_rng;
+        self.robbery_rng = saved_board.robbery_rng;
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
-use std::{iter::repeat, rc::Rc, sync::Arc};
+use std::{collections::HashMap, iter::repeat, rc::Rc, sync::Arc};
 
-use rand::{seq::SliceRandom, thread_rng};
+use rand::seq::SliceRandom;
 
 use crate::game::{
     board::{
@@ -17,62 +17,59 @@ use crate::game::{
             settlement_location::{SettlementLocation, SettlementLocationId},
             settlement_map::SettlementMap,
         },
-        resource::base_resource::{
-            ResourcedId, RESOURCE_CLAY, RESOURCE_ORE, RESOURCE_SHEEP, RESOURCE_WHEAT, RESOURCE_WOOD,
+        resource::{
+            bank::{Bank, BANK_RESOURCE_COUNT},
+            base_resource::Resource,
         },
         trade_contract::{
             AcceptsNAnyTradeContract, AcceptsNSingleResourceTradeContract, TradeContract,
         },
-        GameBoard,
-    },
-    state::states::development_card::{
-        DevelopmentCard, DEVELOPMENT_CARD_INVENTION, DEVELOPMENT_CARD_KNIGHT,
-        DEVELOPMENT_CARD_MONOPOLY, DEVELOPMENT_CARD_STREET_CONSTRUCTION,
-        DEVELOPMENT_CARD_VICTORY_POINT,
+        GameBoard, GeneratedBoard,
     },
+    random::{GameRng, RandomStream},
+    state::states::development_card::DevelopmentCard,
 };
 
-pub fn generate_board() -> Result<GameBoard, String> {
+/// Generates the same board for the same seed
+pub fn generate_board(seed: u64) -> Result<GameBoard, String> {
     let board_size: i32 = 3;
-    let (mut tile_map, board_resources) = match generate_hexagon_map(board_size) {
-        Ok(values) => values,
-        Err(err) => return Err(err),
-    };
-    let dice_chips = match generate_dice_chips(&tile_map) {
-        Ok(chips) => chips,
-        Err(err) => return Err(err),
-    };
+    let mut layout_rng = RandomStream::BoardLayout.create_rng(seed);
+    let (mut tile_map, board_resources) = generate_hexagon_map(board_size, &mut layout_rng)?;
+    let dice_chips = generate_dice_chips(&tile_map, &mut layout_rng)?;
     let robber_location = get_robber_location();
-    let mut settlement_map = match generate_settlement_map(&mut tile_map) {
-        Ok(map) => map,
-        Err(err) => return Err(err),
-    };
-    // if let Err(_) = generate_seaports(&mut settlement_map, &tile_map, board_size) {
-    //     return Err(format!("Failed to generate seaports"));
-    // }
-    let development_cards = match generate_development_cards() {
-        Ok(cards) => cards,
-        Err(err) => return Err(err),
-    };
+    let mut settlement_map = generate_settlement_map(&mut tile_map)?;
+    if let Err(err) = generate_seaports(&mut settlement_map, board_size, &mut layout_rng) {
+        return Err(format!("Failed to generate seaports \"{}\"", err));
+    }
+    let development_cards =
+        generate_development_cards(&mut RandomStream::DevelopmentCards.create_rng(seed))?;
+    let bank = Bank::from(&board_resources, BANK_RESOURCE_COUNT);
 
     Ok(GameBoard::from(
-        tile_map,
-        settlement_map,
-        dice_chips,
-        robber_location,
-        board_resources,
-        development_cards,
+        GeneratedBoard {
+            tile_map,
+            settlement_map,
+            dice_chips,
+            robber_location,
+            board_resources,
+            bank,
+            development_cards,
+        },
+        seed,
     ))
 }
 
-fn generate_hexagon_map(board_size: i32) -> Result<(HexagonMap, Vec<ResourcedId>), String> {
+fn generate_hexagon_map(
+    board_size: i32,
+    rng: &mut GameRng,
+) -> Result<(HexagonMap, Vec<Resource>), String> {
     let mut hexagon_map = HexagonMap::new();
-    let board_resources: Vec<ResourcedId> = vec![
-        RESOURCE_CLAY.to_string(),
-        RESOURCE_WOOD.to_string(),
-        RESOURCE_ORE.to_string(),
-        RESOURCE_SHEEP.to_string(),
-        RESOURCE_WHEAT.to_string(),
+    let board_resources: Vec<Resource> = vec![
+        Resource::Clay,
+        Resource::Wood,
+        Resource::Ore,
+        Resource::Sheep,
+        Resource::Wheat,
     ];
 
     // convert resource count to repeated list of indices
@@ -83,7 +80,7 @@ fn generate_hexagon_map(board_size: i32) -> Result<(HexagonMap, Vec<ResourcedId>
         .into_iter()
         .flat_map(|(index, resource_count)| repeat(index).take(resource_count))
         .collect::<Vec<usize>>();
-    resource_list.shuffle(&mut thread_rng());
+    resource_list.shuffle(rng);
 
     for q in -(board_size - 1)..board_size {
         for r in -(board_size - 1)..board_size {
@@ -94,22 +91,15 @@ fn generate_hexagon_map(board_size: i32) -> Result<(HexagonMap, Vec<ResourcedId>
             }
 
             if coordinates.q == 0 && coordinates.r == 0 && coordinates.s == 0 {
-                if let Err(err) =
-                    hexagon_map.add_tile(HexagonTile::from(coordinates, TileType::FillerTile()))
-                {
-                    return Err(err);
-                }
+                hexagon_map.add_tile(HexagonTile::from(coordinates, TileType::FillerTile))?;
             } else {
-                let tile_resource = board_resources
-                    [resource_list.pop().expect("Not enough resources generated")]
-                .clone();
+                let tile_resource =
+                    board_resources[resource_list.pop().expect("Not enough resources generated")];
 
-                if let Err(err) = hexagon_map.add_tile(HexagonTile::from(
+                hexagon_map.add_tile(HexagonTile::from(
                     coordinates,
                     TileType::ResourceTile(tile_resource),
-                )) {
-                    return Err(err);
-                }
+                ))?;
             }
         }
     }
@@ -117,9 +107,12 @@ fn generate_hexagon_map(board_size: i32) -> Result<(HexagonMap, Vec<ResourcedId>
     Ok((hexagon_map, board_resources))
 }
 
-fn generate_dice_chips(hexagon_map: &HexagonMap) -> Result<Vec<DiceChipLocation>, String> {
+fn generate_dice_chips(
+    hexagon_map: &HexagonMap,
+    rng: &mut GameRng,
+) -> Result<Vec<DiceChipLocation>, String> {
     let mut dice_chip_values = vec![1, 2, 3, 3, 4, 4, 5, 5, 6, 6, 8, 8, 9, 9, 10, 10, 11, 12];
-    dice_chip_values.shuffle(&mut thread_rng());
+    dice_chip_values.shuffle(rng);
 
     let tiles = hexagon_map.get_tiles();
     if dice_chip_values.len() != (tiles.len() - 1) {
@@ -158,9 +151,7 @@ fn generate_settlement_map(hexagon_map: &mut HexagonMap) -> Result<SettlementMap
             let settlement = SettlementLocation::from(neighbor_tiles, None);
 
             if settlement_map.get_settlement(settlement.get_id()).is_none() {
-                if let Err(err) = settlement_map.add_settlement(settlement.clone()) {
-                    return Err(err);
-                }
+                settlement_map.add_settlement(settlement.clone())?;
             }
 
             corner_settlements.push(settlement.get_id().clone());
@@ -177,9 +168,7 @@ fn generate_settlement_map(hexagon_map: &mut HexagonMap) -> Result<SettlementMap
             {
                 continue;
             }
-            if let Err(err) = settlement_map.add_road(road_a) {
-                return Err(err);
-            }
+            settlement_map.add_road(road_a)?;
         }
     }
 
@@ -196,13 +185,13 @@ fn is_tile_in_map(coordinates: &CubeCoordinates, board_size: i32) -> bool {
         && coordinates.s.abs() < board_size
 }
 
-fn generate_development_cards() -> Result<Vec<DevelopmentCard>, String> {
+fn generate_development_cards(rng: &mut GameRng) -> Result<Vec<DevelopmentCard>, String> {
     let mut card_ids: Vec<DevelopmentCard> = vec![
-        DEVELOPMENT_CARD_KNIGHT,
-        DEVELOPMENT_CARD_INVENTION,
-        DEVELOPMENT_CARD_STREET_CONSTRUCTION,
-        DEVELOPMENT_CARD_MONOPOLY,
-        DEVELOPMENT_CARD_VICTORY_POINT,
+        DevelopmentCard::Knight,
+        DevelopmentCard::Invention,
+        DevelopmentCard::StreetConstruction,
+        DevelopmentCard::Monopoly,
+        DevelopmentCard::VictoryPoint,
     ];
     let mut card_frequencies: Vec<usize> = vec![14, 2, 2, 2, 5];
     let mut cards: Vec<DevelopmentCard> = card_frequencies
@@ -211,61 +200,252 @@ fn generate_development_cards() -> Result<Vec<DevelopmentCard>, String> {
         .flat_map(|(index, frequency)| repeat(index).take(frequency))
         .map(|card_index| card_ids[card_index])
         .collect();
-    cards.shuffle(&mut thread_rng());
+    cards.shuffle(rng);
     Ok(cards)
 }
 
-// fn generate_seaports(
-//     settlement_map: &mut SettlementMap,
-//     tile_map: &HexagonMap,
-//     map_size: usize,
-// ) -> Result<(), ()> {
-//     return Ok(());
-
-//     // top edge road
-//     let start_road = match settlement_map.get_road(&RoadLocation::to_id(
-//         SettlementLocation::id_from_tiles(&vec![
-//             CubeCoordinates::from(-1, -2, 3),
-//             CubeCoordinates::from(-1, -1, 2),
-//             CubeCoordinates::from(0, -3, 3),
-//         ]),
-//         SettlementLocation::id_from_tiles(&vec![
-//             CubeCoordinates::from(0, -3, 3),
-//             CubeCoordinates::from(0, -2, 2),
-//             CubeCoordinates::from(1, -3, 2),
-//         ]),
-//     )) {
-//         None => panic!("Road not found"),
-//         Some(road) => road,
-//     };
-
-//     let has_any_seatile = |coordinates: &Vec<CubeCoordinates>, board_size: usize| -> bool {
-//         coordinates
-//             .iter()
-//             .any(|coordinates| is_tile_in_map(coordinates, board_size))
-//     };
-
-//     let mut road_chain: Vec<RoadLocationId> = vec![start_road.get_id().clone()];
-//     let current_road = start_road;
-//     loop {
-//         settlement_map.get_settlement_roads(current_road.get_settlement_a_id());
-//         if (current_road_id == start_road.get_id()) {
-//             break;
-//         }
-//     }
-
-//     let seaport_resources: Vec<ResourcedId> = vec![];
-
-//     if (seaport_edges.len() != seaport_resources.len()) {
-//         panic!("Invalid Seaport count");
-//     }
-
-//     seaport_edges
-//         .iter()
-//         .zip(seaport_resources)
-//         .for_each(|(edge, resource)| {
-//             let trade_contract = AcceptsNSingleResourceTradeContract::new(2, 1, resource);
-//             settlement_map.get_settlement(settlement_id)
-//         });
-//     Err(())
-// }
+fn generate_seaports(
+    settlement_map: &mut SettlementMap,
+    board_size: i32,
+    rng: &mut GameRng,
+) -> Result<(), String> {
+    let coastline = get_coastline(settlement_map, board_size)?;
+
+    let mut trade_contracts: Vec<Arc<dyn TradeContract>> = vec![
+        Resource::Clay,
+        Resource::Wood,
+        Resource::Ore,
+        Resource::Sheep,
+        Resource::Wheat,
+    ]
+    .into_iter()
+    .map(|resource| -> Arc<dyn TradeContract> {
+        Arc::new(AcceptsNSingleResourceTradeContract::new(2, 1, resource))
+    })
+    .collect();
+    for _ in 0..4 {
+        trade_contracts.push(Arc::new(AcceptsNAnyTradeContract::new(3, 1)));
+    }
+    trade_contracts.shuffle(rng);
+
+    // seaports must not share a settlement with another seaport
+    if coastline.len() < trade_contracts.len() * 2 {
+        return Err(format!(
+            "Coastline with {} roads is too short for {} seaports",
+            coastline.len(),
+            trade_contracts.len()
+        ));
+    }
+
+    let seaport_count = trade_contracts.len();
+    for (seaport_index, trade_contract) in trade_contracts.into_iter().enumerate() {
+        let road_id = &coastline[seaport_index * coastline.len() / seaport_count];
+        let road = match settlement_map.get_road(road_id) {
+            None => return Err(format!("Coastline road \"{}\" not found", road_id)),
+            Some(road) => road.clone(),
+        };
+
+        let seaport = SeaportLocation::new(trade_contract);
+        for settlement_id in [road.get_settlement_a_id(), road.get_settlement_b_id()] {
+            match settlement_map.get_settlement_mut(settlement_id) {
+                None => {
+                    return Err(format!(
+                        "Seaport settlement \"{}\" not found",
+                        settlement_id
+                    ))
+                }
+                Some(settlement) => settlement.set_seaport(seaport.clone()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Roads between a land and a sea tile in the order of the outer ring of the map
+fn get_coastline(
+    settlement_map: &SettlementMap,
+    board_size: i32,
+) -> Result<Vec<RoadLocationId>, String> {
+    let mut coastal_roads: Vec<&RoadLocation> = settlement_map
+        .get_roads()
+        .into_iter()
+        .filter(|road| is_coastal_road(settlement_map, road, board_size))
+        .collect();
+    coastal_roads.sort_by(|a, b| a.get_id().cmp(b.get_id()));
+
+    let mut settlement_coastal_roads = HashMap::<&SettlementLocationId, Vec<&RoadLocation>>::new();
+    for road in coastal_roads.iter() {
+        for settlement_id in [road.get_settlement_a_id(), road.get_settlement_b_id()] {
+            settlement_coastal_roads
+                .entry(settlement_id)
+                .or_default()
+                .push(road);
+        }
+    }
+
+    if settlement_coastal_roads
+        .values()
+        .any(|roads| roads.len() != 2)
+    {
+        return Err("Coastline is not a single ring".to_string());
+    }
+
+    let start_road = match coastal_roads.first() {
+        None => return Err("Map has no coastline".to_string()),
+        Some(road) => *road,
+    };
+
+    let mut coastline: Vec<RoadLocationId> = vec![];
+    let mut current_road = start_road;
+    let mut current_settlement_id = start_road.get_settlement_b_id();
+    loop {
+        coastline.push(current_road.get_id().clone());
+
+        let next_road = match settlement_coastal_roads[current_settlement_id]
+            .iter()
+            .find(|road| road.get_id() != current_road.get_id())
+        {
+            None => return Err("Coastline is interrupted".to_string()),
+            Some(road) => *road,
+        };
+
+        if next_road.get_id() == start_road.get_id() {
+            break;
+        }
+
+        current_settlement_id = if next_road.get_settlement_a_id() == current_settlement_id {
+            next_road.get_settlement_b_id()
+        } else {
+            next_road.get_settlement_a_id()
+        };
+        current_road = next_road;
+    }
+
+    if coastline.len() != coastal_roads.len() {
+        return Err("Coastline is not a single ring".to_string());
+    }
+
+    Ok(coastline)
+}
+
+/// A coastal road separates a land tile from a sea tile
+fn is_coastal_road(settlement_map: &SettlementMap, road: &RoadLocation, board_size: i32) -> bool {
+    let (settlement_a, settlement_b) = match (
+        settlement_map.get_settlement(road.get_settlement_a_id()),
+        settlement_map.get_settlement(road.get_settlement_b_id()),
+    ) {
+        (Some(settlement_a), Some(settlement_b)) => (settlement_a, settlement_b),
+        _ => return false,
+    };
+
+    let road_tiles: Vec<&CubeCoordinates> = settlement_a
+        .get_neighbor_tiles()
+        .iter()
+        .filter(|tile| settlement_b.get_neighbor_tiles().contains(tile))
+        .collect();
+
+    road_tiles.len() == 2
+        && road_tiles
+            .iter()
+            .filter(|tile| is_tile_in_map(tile, board_size))
+            .count()
+            == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::game::board::location::{
+        settlement_location::SettlementLocation, settlement_map::SettlementMap,
+    };
+
+    use super::{generate_board, get_coastline, is_tile_in_map};
+
+    const BOARD_SIZE: i32 = 3;
+
+    fn get_seaport_settlements(settlement_map: &SettlementMap) -> Vec<&SettlementLocation> {
+        let mut settlements: Vec<&SettlementLocation> = settlement_map
+            .get_settlements()
+            .into_iter()
+            .filter(|settlement| settlement.get_seaport().is_some())
+            .collect();
+        settlements.sort_by(|a, b| a.get_id().cmp(b.get_id()));
+        settlements
+    }
+
+    #[test]
+    fn coastline_is_a_single_ring() {
+        let board = generate_board(7).unwrap();
+        let coastline = get_coastline(board.get_settlement_map(), BOARD_SIZE).unwrap();
+
+        assert_eq!(coastline.len(), 30);
+    }
+
+    #[test]
+    fn seaports_are_placed_on_coastal_roads() {
+        let board = generate_board(7).unwrap();
+        let settlement_map = board.get_settlement_map();
+        let seaport_settlements = get_seaport_settlements(settlement_map);
+
+        // 9 seaports with two settlements each, no settlement is shared
+        assert_eq!(seaport_settlements.len(), 18);
+        for settlement in seaport_settlements.iter() {
+            assert!(settlement
+                .get_neighbor_tiles()
+                .iter()
+                .any(|tile| !is_tile_in_map(tile, BOARD_SIZE)));
+
+            let seaport = settlement.get_seaport().as_ref().unwrap();
+            let partner_count = settlement_map
+                .get_neighbor_settlements(settlement.get_id())
+                .into_iter()
+                .filter_map(|neighbor| neighbor.get_seaport().as_ref())
+                .filter(|neighbor_seaport| {
+                    Arc::ptr_eq(
+                        neighbor_seaport.get_trade_contract(),
+                        seaport.get_trade_contract(),
+                    )
+                })
+                .count();
+            assert_eq!(partner_count, 1);
+        }
+
+        let single_resource_count = seaport_settlements
+            .iter()
+            .filter_map(|settlement| settlement.get_seaport().as_ref())
+            .filter(|seaport| seaport.get_trade_contract().get_resource().is_some())
+            .count();
+        assert_eq!(single_resource_count, 10);
+    }
+
+    #[test]
+    fn seaports_are_placed_by_seed() {
+        let board_a = generate_board(7).unwrap();
+        let board_b = generate_board(7).unwrap();
+
+        let seaports = |settlement_map| -> Vec<(String, Option<String>)> {
+            get_seaport_settlements(settlement_map)
+                .into_iter()
+                .map(|settlement| {
+                    let trade_contract = settlement
+                        .get_seaport()
+                        .as_ref()
+                        .unwrap()
+                        .get_trade_contract();
+                    (
+                        settlement.get_id().clone(),
+                        trade_contract
+                            .get_resource()
+                            .map(|resource| format!("{:?}", resource)),
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(
+            seaports(board_a.get_settlement_map()),
+            seaports(board_b.get_settlement_map())
+        );
+    }
+}
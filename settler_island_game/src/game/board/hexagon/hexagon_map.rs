@@ -46,7 +46,10 @@ impl HexagonMap {
             .collect()
     }
 
+    /// Tiles ordered by their coordinates
     pub fn get_tiles(&self) -> Vec<&RefCell<HexagonTile>> {
-        self.tiles.values().collect()
+        let mut tiles: Vec<(&CubeCoordinates, &RefCell<HexagonTile>)> = self.tiles.iter().collect();
+        tiles.sort_by_key(|(coordinates, _)| **coordinates);
+        tiles.into_iter().map(|(_, tile)| tile).collect()
     }
 }
@@ -1,12 +1,17 @@
+use serde::Serialize;
+
 use crate::game::board::{
-    location::settlement_location::SettlementLocationId, resource::base_resource::ResourcedId,
+    location::settlement_location::SettlementLocationId, resource::base_resource::Resource,
 };
 
 use super::cube_coordinates::CubeCoordinates;
 
+/// Serialized as `{"type": "ResourceTile", "resource": "Ore"}`
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(tag = "type", content = "resource")]
 pub enum TileType {
-    FillerTile(),
-    ResourceTile(ResourcedId),
+    FillerTile,
+    ResourceTile(Resource),
 }
 
 pub struct HexagonTile {
@@ -75,4 +75,11 @@ impl RoadLocation {
     pub fn get_player_road(&self) -> &Option<PlayerRoad> {
         &self.player_road
     }
+
+    pub fn is_owner(&self, player_id: &usize) -> bool {
+        match self.player_road.as_ref() {
+            Some(road) => road.get_player_id() == player_id,
+            None => false,
+        }
+    }
 }
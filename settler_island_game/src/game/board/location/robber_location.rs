@@ -1,8 +1,8 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::game::board::hexagon::cube_coordinates::CubeCoordinates;
 
-#[derive(Serialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct RobberLocation {
     assigned_tile: CubeCoordinates,
 }
@@ -12,7 +12,7 @@ pub enum SettlementType {
     City,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PlayerSettlement {
     player_id: usize,
     settlement_type: SettlementType,
@@ -46,10 +46,22 @@ impl SettlementLocation {
         &self.id
     }
 
+    pub fn get_neighbor_tiles(&self) -> &Vec<CubeCoordinates> {
+        &self.neighbor_tiles
+    }
+
     pub fn get_settlement(&self) -> &Option<PlayerSettlement> {
         &self.settlement
     }
 
+    pub fn get_seaport(&self) -> &Option<SeaportLocation> {
+        &self.seaport
+    }
+
+    pub fn set_seaport(&mut self, seaport: SeaportLocation) {
+        self.seaport = Some(seaport);
+    }
+
     pub fn destroy_settlement(&mut self) {
         self.settlement = None
     }
@@ -65,6 +77,22 @@ impl SettlementLocation {
         }
     }
 
+    /// Villages need a free location, cities replace a village of the player
+    pub fn can_build_settlement(
+        &self,
+        settlement_type: &SettlementType,
+        player_id: &usize,
+    ) -> bool {
+        match (settlement_type, self.settlement.as_ref()) {
+            (SettlementType::Village, None) => true,
+            (SettlementType::City, Some(settlement)) => {
+                settlement.get_player_id() == player_id
+                    && matches!(settlement.get_settlement_type(), SettlementType::Village)
+            }
+            _ => false,
+        }
+    }
+
     fn build_village(&mut self, player_id: &usize) -> Result<(), String> {
         if self.settlement.is_some() {
             return Err(format!(
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use super::{
     road_location::{RoadLocation, RoadLocationId},
@@ -28,6 +31,10 @@ impl SettlementMap {
         self.roads.get(road_id)
     }
 
+    pub fn get_roads(&self) -> Vec<&RoadLocation> {
+        self.roads.values().collect()
+    }
+
     pub fn get_road_mut(&mut self, road_id: &RoadLocationId) -> Option<&mut RoadLocation> {
         self.roads.get_mut(road_id)
     }
@@ -43,6 +50,10 @@ impl SettlementMap {
         self.settlements.get(settlement_id)
     }
 
+    pub fn get_settlements(&self) -> Vec<&SettlementLocation> {
+        self.settlements.values().collect()
+    }
+
     pub fn get_settlement_mut(
         &mut self,
         settlement_id: &SettlementLocationId,
@@ -147,6 +158,27 @@ impl SettlementMap {
             })
     }
 
+    /// Checks if the road continues a road of the player.
+    /// Roads cannot be continued through a settlement of another player
+    pub fn is_road_connected_to_player_road(
+        &self,
+        road_id: &RoadLocationId,
+        player_id: &usize,
+    ) -> bool {
+        let road = match self.roads.get(road_id) {
+            None => return false,
+            Some(road) => road,
+        };
+
+        [road.get_settlement_a_id(), road.get_settlement_b_id()]
+            .into_iter()
+            .filter(|settlement_id| !self.is_blocked_for_player(settlement_id, player_id))
+            .flat_map(|settlement_id| self.get_settlement_roads(settlement_id))
+            .any(|connected_road| {
+                connected_road.get_id() != road_id && connected_road.is_owner(player_id)
+            })
+    }
+
     pub fn get_player_settlements(&self, player_id: &usize) -> Vec<&SettlementLocation> {
         self.settlements
             .values()
@@ -160,4 +192,132 @@ impl SettlementMap {
             .iter()
             .any(|location| location.get_settlement().is_some())
     }
+
+    /// Length of the longest continuous road of the player.
+    /// A settlement of another player interrupts the road
+    pub fn get_longest_road_length(&self, player_id: &usize) -> usize {
+        let mut visited_roads = HashSet::<RoadLocationId>::new();
+        self.roads
+            .values()
+            .filter(|road| road.is_owner(player_id))
+            .flat_map(|road| [road.get_settlement_a_id(), road.get_settlement_b_id()])
+            .map(|settlement_id| {
+                self.get_road_chain_length(settlement_id, player_id, &mut visited_roads)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn get_road_chain_length(
+        &self,
+        settlement_id: &SettlementLocationId,
+        player_id: &usize,
+        visited_roads: &mut HashSet<RoadLocationId>,
+    ) -> usize {
+        let mut chain_length = 0;
+        for road in self.get_settlement_roads(settlement_id) {
+            if !road.is_owner(player_id) || visited_roads.contains(road.get_id()) {
+                continue;
+            }
+
+            let next_settlement_id = if road.get_settlement_a_id() == settlement_id {
+                road.get_settlement_b_id()
+            } else {
+                road.get_settlement_a_id()
+            };
+
+            visited_roads.insert(road.get_id().clone());
+            let next_chain_length = if self.is_blocked_for_player(next_settlement_id, player_id) {
+                0
+            } else {
+                self.get_road_chain_length(next_settlement_id, player_id, visited_roads)
+            };
+            visited_roads.remove(road.get_id());
+
+            chain_length = chain_length.max(1 + next_chain_length);
+        }
+        chain_length
+    }
+
+    fn is_blocked_for_player(
+        &self,
+        settlement_id: &SettlementLocationId,
+        player_id: &usize,
+    ) -> bool {
+        match self.settlements.get(settlement_id) {
+            None => false,
+            Some(settlement) => {
+                settlement.get_settlement().is_some() && !settlement.is_owner(player_id)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::{
+        hexagon::cube_coordinates::CubeCoordinates,
+        location::{
+            road_location::{PlayerRoad, RoadLocation},
+            settlement_location::{SettlementLocation, SettlementLocationId, SettlementType},
+        },
+    };
+
+    use super::SettlementMap;
+
+    /// Straight line of settlements connected by roads of the player
+    fn build_road_chain(
+        road_count: i32,
+        player_id: usize,
+    ) -> (SettlementMap, Vec<SettlementLocationId>) {
+        let mut settlement_map = SettlementMap::new();
+        let settlement_ids: Vec<SettlementLocationId> = (0..=road_count)
+            .map(|q| {
+                let settlement =
+                    SettlementLocation::from(vec![CubeCoordinates::from_qr(q, 0)], None);
+                let settlement_id = settlement.get_id().clone();
+                settlement_map.add_settlement(settlement).unwrap();
+                settlement_id
+            })
+            .collect();
+
+        for road_ids in settlement_ids.windows(2) {
+            let mut road = RoadLocation::from(road_ids[0].clone(), road_ids[1].clone());
+            road.build_road(PlayerRoad::from(player_id)).unwrap();
+            settlement_map.add_road(road).unwrap();
+        }
+        (settlement_map, settlement_ids)
+    }
+
+    #[test]
+    fn longest_road_counts_connected_roads() {
+        let (settlement_map, _) = build_road_chain(6, 0);
+
+        assert_eq!(settlement_map.get_longest_road_length(&0), 6);
+        assert_eq!(settlement_map.get_longest_road_length(&1), 0);
+    }
+
+    #[test]
+    fn longest_road_is_broken_by_opponent_settlement() {
+        let (mut settlement_map, settlement_ids) = build_road_chain(6, 0);
+        settlement_map
+            .get_settlement_mut(&settlement_ids[2])
+            .unwrap()
+            .build_settlement(SettlementType::Village, &1)
+            .unwrap();
+
+        assert_eq!(settlement_map.get_longest_road_length(&0), 4);
+    }
+
+    #[test]
+    fn longest_road_is_not_broken_by_own_settlement() {
+        let (mut settlement_map, settlement_ids) = build_road_chain(6, 0);
+        settlement_map
+            .get_settlement_mut(&settlement_ids[2])
+            .unwrap()
+            .build_settlement(SettlementType::Village, &0)
+            .unwrap();
+
+        assert_eq!(settlement_map.get_longest_road_length(&0), 6);
+    }
 }
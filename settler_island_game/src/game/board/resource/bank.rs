@@ -0,0 +1,56 @@
+use super::{
+    base_resource::Resource,
+    player_resources::{PlayerResources, ResourceCollection},
+};
+
+pub const BANK_RESOURCE_COUNT: usize = 19;
+
+/// Finite supply of resource cards.
+/// Resources paid by players are returned to the bank and
+/// resources received by players are taken from the bank
+pub struct Bank {
+    resources: PlayerResources,
+}
+
+impl Bank {
+    pub fn from(resources: &[Resource], resource_count: usize) -> Self {
+        let mut bank_resources = PlayerResources::new();
+        resources
+            .iter()
+            .for_each(|resource| bank_resources.add_resource(resource, &resource_count));
+
+        Bank {
+            resources: bank_resources,
+        }
+    }
+
+    pub fn from_resources(resources: ResourceCollection) -> Self {
+        let mut bank_resources = PlayerResources::new();
+        bank_resources.add_resources(resources);
+
+        Bank {
+            resources: bank_resources,
+        }
+    }
+
+    pub fn get_resources(&self) -> &PlayerResources {
+        &self.resources
+    }
+
+    pub fn get_resource_count(&self, resource: &Resource) -> usize {
+        self.resources.get_resource(resource)
+    }
+
+    pub fn has_resources(&self, resources: &ResourceCollection) -> bool {
+        self.resources.has_resources(resources)
+    }
+
+    pub fn deposit_resources(&mut self, resources: ResourceCollection) {
+        self.resources.add_resources(resources);
+    }
+
+    /// Takes all resources or none if the bank cannot provide every resource
+    pub fn withdraw_resources(&mut self, resources: &ResourceCollection) -> bool {
+        self.resources.remove_resources(resources)
+    }
+}
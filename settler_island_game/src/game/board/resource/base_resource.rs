@@ -1,7 +1,33 @@
-pub type ResourcedId = String;
+use serde::{Deserialize, Serialize};
 
-pub const RESOURCE_CLAY: &'static str = "Clay";
-pub const RESOURCE_ORE: &'static str = "Ore";
-pub const RESOURCE_WOOD: &'static str = "Wood";
-pub const RESOURCE_WHEAT: &'static str = "Wheat";
-pub const RESOURCE_SHEEP: &'static str = "Sheep";
+pub const RESOURCE_TYPE_COUNT: usize = 5;
+
+/// Resource types serialize to their names, e.g. `"Clay"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Resource {
+    Clay,
+    Ore,
+    Wood,
+    Wheat,
+    Sheep,
+}
+
+impl Resource {
+    pub const ALL: [Resource; RESOURCE_TYPE_COUNT] = [
+        Resource::Clay,
+        Resource::Ore,
+        Resource::Wood,
+        Resource::Wheat,
+        Resource::Sheep,
+    ];
+
+    pub fn get_index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
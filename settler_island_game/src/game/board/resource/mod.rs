@@ -1,2 +1,3 @@
+pub mod bank;
 pub mod base_resource;
 pub mod player_resources;
@@ -1,11 +1,140 @@
-use std::{collections::HashMap, ops::Add};
+use std::{
+    collections::BTreeMap,
+    ops::{Add, AddAssign, Index, IndexMut, Mul},
+};
 
-use serde::de::value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use super::base_resource::ResourcedId;
+use super::base_resource::{Resource, RESOURCE_TYPE_COUNT};
 
-pub type ResourceCollection = HashMap<ResourcedId, usize>;
+/// Count of every resource type.
+/// Serializes to a map of resource names to counts, e.g. `{"Clay": 1, "Wood": 1}`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceCollection {
+    counts: [usize; RESOURCE_TYPE_COUNT],
+}
+
+impl ResourceCollection {
+    pub fn new() -> Self {
+        ResourceCollection {
+            counts: [0; RESOURCE_TYPE_COUNT],
+        }
+    }
+
+    pub fn get(&self, resource: &Resource) -> usize {
+        self.counts[resource.get_index()]
+    }
+
+    pub fn set(&mut self, resource: &Resource, count: usize) {
+        self.counts[resource.get_index()] = count;
+    }
+
+    /// Resources with a count greater than zero
+    pub fn iter(&self) -> impl Iterator<Item = (Resource, usize)> + '_ {
+        Resource::ALL
+            .into_iter()
+            .map(|resource| (resource, self.get(&resource)))
+            .filter(|(_, count)| *count > 0)
+    }
+
+    pub fn get_total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.get_total() == 0
+    }
+
+    /// Checks if every resource count is at least the count in `other`
+    pub fn contains(&self, other: &ResourceCollection) -> bool {
+        Resource::ALL
+            .iter()
+            .all(|resource| self.get(resource) >= other.get(resource))
+    }
+
+    /// Subtracts `other` or returns `None` if a resource count would drop below zero
+    pub fn checked_sub(&self, other: &ResourceCollection) -> Option<ResourceCollection> {
+        if !self.contains(other) {
+            return None;
+        }
+        let mut difference = *self;
+        for index in 0..RESOURCE_TYPE_COUNT {
+            difference.counts[index] -= other.counts[index];
+        }
+        Some(difference)
+    }
+}
+
+impl<const N: usize> From<[(Resource, usize); N]> for ResourceCollection {
+    fn from(resources: [(Resource, usize); N]) -> Self {
+        let mut collection = ResourceCollection::new();
+        for (resource, count) in resources {
+            collection[resource] += count;
+        }
+        collection
+    }
+}
+
+impl Index<Resource> for ResourceCollection {
+    type Output = usize;
+
+    fn index(&self, resource: Resource) -> &Self::Output {
+        &self.counts[resource.get_index()]
+    }
+}
 
+impl IndexMut<Resource> for ResourceCollection {
+    fn index_mut(&mut self, resource: Resource) -> &mut Self::Output {
+        &mut self.counts[resource.get_index()]
+    }
+}
+
+impl Add for ResourceCollection {
+    type Output = ResourceCollection;
+
+    fn add(mut self, other: ResourceCollection) -> Self::Output {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for ResourceCollection {
+    fn add_assign(&mut self, other: ResourceCollection) {
+        for index in 0..RESOURCE_TYPE_COUNT {
+            self.counts[index] += other.counts[index];
+        }
+    }
+}
+
+impl Mul<usize> for ResourceCollection {
+    type Output = ResourceCollection;
+
+    fn mul(mut self, factor: usize) -> Self::Output {
+        self.counts.iter_mut().for_each(|count| *count *= factor);
+        self
+    }
+}
+
+impl Serialize for ResourceCollection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let resources: BTreeMap<Resource, usize> = self.iter().collect();
+        resources.serialize(serializer)
+    }
+}
+
+/// Unknown resource names are rejected
+impl<'de> Deserialize<'de> for ResourceCollection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let resources = BTreeMap::<Resource, usize>::deserialize(deserializer)?;
+        let mut collection = ResourceCollection::new();
+        for (resource, count) in resources {
+            collection[resource] = count;
+        }
+        Ok(collection)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PlayerResources {
     resources: ResourceCollection,
 }
@@ -13,76 +142,58 @@ pub struct PlayerResources {
 impl PlayerResources {
     pub fn new() -> Self {
         PlayerResources {
-            resources: HashMap::new(),
+            resources: ResourceCollection::new(),
         }
     }
 
     pub fn get_resources(&self) -> ResourceCollection {
-        self.resources.clone()
+        self.resources
     }
 
-    pub fn add_resource(&mut self, resource: &ResourcedId, count: &usize) {
-        match self.resources.get(resource) {
-            None => self.resources.insert(resource.clone(), *count),
-            Some(resource_count) => self
-                .resources
-                .insert(resource.clone(), resource_count + count),
-        };
+    pub fn add_resource(&mut self, resource: &Resource, count: &usize) {
+        self.resources[*resource] += count;
     }
 
     pub fn add_resources(&mut self, added_resources: ResourceCollection) {
-        added_resources
-            .into_iter()
-            .for_each(|(resource, count)| self.add_resource(&resource, &count))
+        self.resources += added_resources;
     }
 
-    pub fn remove_resource(&mut self, resource: &ResourcedId, count: &usize) -> bool {
-        match self.resources.get(resource) {
-            None => false,
-            Some(resource_count) => {
-                if resource_count < count {
-                    return false;
-                }
-                self.resources
-                    .insert(resource.clone(), resource_count - count)
-                    .is_some()
-            }
+    pub fn remove_resource(&mut self, resource: &Resource, count: &usize) -> bool {
+        if !self.has_resource(resource, count) {
+            return false;
         }
+        self.resources[*resource] -= count;
+        true
     }
 
+    /// Removes all resources or none if one of them is missing
     pub fn remove_resources(&mut self, removed_resources: &ResourceCollection) -> bool {
-        removed_resources
-            .into_iter()
-            .any(|(resource, count)| self.remove_resource(&resource, &count))
+        match self.resources.checked_sub(removed_resources) {
+            None => false,
+            Some(resources) => {
+                self.resources = resources;
+                true
+            }
+        }
     }
 
-    pub fn get_resource(&self, resource: &ResourcedId) -> Option<&usize> {
+    pub fn get_resource(&self, resource: &Resource) -> usize {
         self.resources.get(resource)
     }
 
     pub fn get_total_resources(&self) -> usize {
-        get_total_resources(&self.resources)
+        self.resources.get_total()
     }
 
-    pub fn has_resource(&self, resource: &ResourcedId, expected_count: &usize) -> bool {
-        self.resources.get(resource).unwrap_or(&0) >= expected_count
+    pub fn has_resource(&self, resource: &Resource, expected_count: &usize) -> bool {
+        self.resources.get(resource) >= *expected_count
     }
 
     pub fn has_resources(&self, checked_resources: &ResourceCollection) -> bool {
-        checked_resources
-            .into_iter()
-            .all(|(resource, count)| self.has_resource(&resource, &count))
+        self.resources.contains(checked_resources)
     }
 }
 
 pub fn get_total_resources(resource_collection: &ResourceCollection) -> usize {
-    match resource_collection
-        .values()
-        .into_iter()
-        .map(|value| *value)
-        .reduce(|a, b| a + b)
-    {
-        None => 0,
-        Some(sum) => sum,
-    }
+    resource_collection.get_total()
 }
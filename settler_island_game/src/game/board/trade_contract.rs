@@ -1,14 +1,19 @@
 use std::fmt::Debug;
 
 use super::resource::{
-    base_resource::ResourcedId,
+    base_resource::Resource,
     player_resources::{get_total_resources, ResourceCollection},
 };
 
 pub trait TradeContract: Sync + Send {
     fn accepts_offer(&self, receive: ResourceCollection, send: ResourceCollection) -> bool;
+    fn get_receive_count(&self) -> usize;
+    fn get_send_count(&self) -> usize;
+    /// Resource type the contract is limited to, `None` if any type is accepted
+    fn get_resource(&self) -> Option<Resource>;
 }
 
+/// Accepts `receive_count` resources of the same type for `send_count` resources
 #[derive(Debug, Clone)]
 pub struct AcceptsNAnyTradeContract {
     receive_count: usize,
@@ -37,24 +42,41 @@ impl TradeContract for AcceptsNAnyTradeContract {
             return false;
         }
 
-        if receive_count % self.receive_count != 0 {
+        // every received resource type has to be traded in full bundles
+        if receive
+            .iter()
+            .any(|(_, resource_count)| !resource_count.is_multiple_of(self.receive_count))
+        {
             return false;
         }
 
         let trade_times = receive_count / self.receive_count;
         (trade_times * self.send_count) == send_count
     }
+
+    fn get_receive_count(&self) -> usize {
+        self.receive_count
+    }
+
+    fn get_send_count(&self) -> usize {
+        self.send_count
+    }
+
+    fn get_resource(&self) -> Option<Resource> {
+        None
+    }
 }
 
+/// Accepts `receive_count` resources of a single resource type for `send_count` resources
 #[derive(Debug, Clone)]
 pub struct AcceptsNSingleResourceTradeContract {
     receive_count: usize,
     send_count: usize,
-    resource: ResourcedId,
+    resource: Resource,
 }
 
 impl AcceptsNSingleResourceTradeContract {
-    pub fn new(receive_count: usize, send_count: usize, resource: ResourcedId) -> Self {
+    pub fn new(receive_count: usize, send_count: usize, resource: Resource) -> Self {
         if receive_count == 0 || send_count == 0 {
             panic!("Invalid trade contract");
         }
@@ -76,7 +98,7 @@ impl TradeContract for AcceptsNSingleResourceTradeContract {
             return false;
         }
 
-        if receive_count % self.receive_count != 0 {
+        if !receive_count.is_multiple_of(self.receive_count) {
             return false;
         }
 
@@ -86,6 +108,18 @@ impl TradeContract for AcceptsNSingleResourceTradeContract {
             return false;
         }
 
-        receive.get(&self.resource).unwrap_or(&0) == &receive_count
+        receive.get(&self.resource) == receive_count
+    }
+
+    fn get_receive_count(&self) -> usize {
+        self.receive_count
+    }
+
+    fn get_send_count(&self) -> usize {
+        self.send_count
+    }
+
+    fn get_resource(&self) -> Option<Resource> {
+        Some(self.resource)
     }
 }
@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct Color {
     r: f32,
     g: f32,
@@ -8,6 +8,34 @@ pub struct Color {
     a: f32,
 }
 
+/// Distinct color of every seat, the seat index is the player id
+pub const SEAT_COLORS: [Color; 4] = [
+    Color {
+        r: 0.85,
+        g: 0.15,
+        b: 0.15,
+        a: 1.0,
+    },
+    Color {
+        r: 0.15,
+        g: 0.35,
+        b: 0.85,
+        a: 1.0,
+    },
+    Color {
+        r: 0.95,
+        g: 0.95,
+        b: 0.95,
+        a: 1.0,
+    },
+    Color {
+        r: 0.95,
+        g: 0.55,
+        b: 0.1,
+        a: 1.0,
+    },
+];
+
 impl Color {
     pub fn from(r: f32, b: f32, g: f32, a: f32) -> Self {
         Color {
@@ -17,4 +45,8 @@ impl Color {
             a: a,
         }
     }
+
+    pub fn from_seat(seat: usize) -> Option<Self> {
+        SEAT_COLORS.get(seat).copied()
+    }
 }
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::{
+        hexagon::cube_coordinates::CubeCoordinates,
+        location::{road_location::RoadLocationId, settlement_location::SettlementLocationId},
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
+        DoubleDiceRoll,
+    },
+    player::PlayerId,
+    state::states::development_card::DevelopmentCard,
+};
+
+/// Change of the game caused by a successful action.
+/// Events are serialized like actions, e.g. `{"id": "RoadBuilt", "data": {"player_id": 0, ...}}`.
+/// Events contain hidden information such as bought cards and stolen resources,
+/// use [GameEvent::for_viewer] before sending them to other players
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "id", content = "data")]
+pub enum GameEvent {
+    DiceRolled {
+        player_id: PlayerId,
+        dice_roll: DoubleDiceRoll,
+    },
+    /// Resources paid by the bank after a dice roll or for the second starting village
+    ResourcesProduced {
+        resources: BTreeMap<PlayerId, ResourceCollection>,
+    },
+    RoadBuilt {
+        player_id: PlayerId,
+        road_id: RoadLocationId,
+    },
+    SettlementBuilt {
+        player_id: PlayerId,
+        settlement_id: SettlementLocationId,
+    },
+    CityUpgraded {
+        player_id: PlayerId,
+        settlement_id: SettlementLocationId,
+    },
+    /// `card` is `None` in the views of other players
+    CardBought {
+        player_id: PlayerId,
+        card: Option<DevelopmentCard>,
+    },
+    CardPlayed {
+        player_id: PlayerId,
+        card: DevelopmentCard,
+    },
+    RobberMoved {
+        player_id: PlayerId,
+        tile_location: CubeCoordinates,
+    },
+    /// `resource` is `None` in the views of players who were not part of the robbery
+    ResourceStolen {
+        player_id: PlayerId,
+        robbed_player_id: PlayerId,
+        resource: Option<Resource>,
+    },
+    /// Resources taken from the other players with a monopoly card, keyed by the robbed players
+    ResourcesMonopolized {
+        player_id: PlayerId,
+        resources: BTreeMap<PlayerId, ResourceCollection>,
+    },
+    /// Resources paid by the bank for an invention card
+    ResourcesInvented {
+        player_id: PlayerId,
+        resources: ResourceCollection,
+    },
+    /// Resources returned to the bank by a player holding too many cards after a seven
+    ResourcesRemoved {
+        player_id: PlayerId,
+        resources: ResourceCollection,
+    },
+    /// Trade between two players or with the bank if `partner_id` is `None`
+    TradeCompleted {
+        player_id: PlayerId,
+        partner_id: Option<PlayerId>,
+        resource_offer: ResourceCollection,
+        resource_receive: ResourceCollection,
+    },
+    TurnEnded {
+        player_id: PlayerId,
+        turn: usize,
+    },
+    GameWon {
+        player_id: PlayerId,
+        victory_points: usize,
+    },
+}
+
+impl GameEvent {
+    /// Copy of the event without the information hidden from the viewer,
+    /// spectators are passed as `None`
+    pub fn for_viewer(&self, viewer_id: Option<&PlayerId>) -> GameEvent {
+        match self {
+            GameEvent::CardBought { player_id, .. } if viewer_id != Some(player_id) => {
+                GameEvent::CardBought {
+                    player_id: *player_id,
+                    card: None,
+                }
+            }
+            GameEvent::ResourceStolen {
+                player_id,
+                robbed_player_id,
+                ..
+            } if viewer_id != Some(player_id) && viewer_id != Some(robbed_player_id) => {
+                GameEvent::ResourceStolen {
+                    player_id: *player_id,
+                    robbed_player_id: *robbed_player_id,
+                    resource: None,
+                }
+            }
+            event => event.clone(),
+        }
+    }
+}
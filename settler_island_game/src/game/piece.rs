@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::location::settlement_location::SettlementType;
+
+pub const DEFAULT_ROAD_PIECES: usize = 15;
+pub const DEFAULT_VILLAGE_PIECES: usize = 5;
+pub const DEFAULT_CITY_PIECES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PieceType {
+    Road,
+    Village,
+    City,
+}
+
+impl PieceType {
+    pub fn from_settlement_type(settlement_type: &SettlementType) -> Self {
+        match settlement_type {
+            SettlementType::Village => PieceType::Village,
+            SettlementType::City => PieceType::City,
+        }
+    }
+}
+
+/// Building pieces a player has not placed on the board yet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PieceInventory {
+    roads: usize,
+    villages: usize,
+    cities: usize,
+}
+
+impl PieceInventory {
+    pub fn new(roads: usize, villages: usize, cities: usize) -> Self {
+        PieceInventory {
+            roads,
+            villages,
+            cities,
+        }
+    }
+
+    pub fn get_piece_count(&self, piece_type: &PieceType) -> usize {
+        match piece_type {
+            PieceType::Road => self.roads,
+            PieceType::Village => self.villages,
+            PieceType::City => self.cities,
+        }
+    }
+
+    pub fn has_piece(&self, piece_type: &PieceType) -> bool {
+        self.get_piece_count(piece_type) > 0
+    }
+
+    pub fn take_piece(&mut self, piece_type: &PieceType) -> bool {
+        let piece_count = self.get_piece_count_mut(piece_type);
+        if *piece_count == 0 {
+            return false;
+        }
+        *piece_count -= 1;
+        true
+    }
+
+    /// Pieces return to the inventory when a village is upgraded to a city
+    pub fn return_piece(&mut self, piece_type: &PieceType) {
+        *self.get_piece_count_mut(piece_type) += 1;
+    }
+
+    fn get_piece_count_mut(&mut self, piece_type: &PieceType) -> &mut usize {
+        match piece_type {
+            PieceType::Road => &mut self.roads,
+            PieceType::Village => &mut self.villages,
+            PieceType::City => &mut self.cities,
+        }
+    }
+}
+
+impl Default for PieceInventory {
+    fn default() -> Self {
+        PieceInventory::new(
+            DEFAULT_ROAD_PIECES,
+            DEFAULT_VILLAGE_PIECES,
+            DEFAULT_CITY_PIECES,
+        )
+    }
+}
@@ -1,26 +1,75 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 
 use super::{
-    board::resource::player_resources::PlayerResources, color::Color,
+    board::resource::player_resources::PlayerResources, color::Color, piece::PieceInventory,
     state::states::development_card::DevelopmentCard,
 };
 
 pub type PlayerId = usize;
-pub type DevelopmentCards = HashMap<String, usize>;
+pub type DevelopmentCards = Vec<HeldDevelopmentCard>;
+pub type PlayedDevelopmentCards = BTreeMap<DevelopmentCard, usize>;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HeldDevelopmentCard {
+    card: DevelopmentCard,
+    acquired_turn: usize,
+}
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Player {
     id: PlayerId,
     user_id: Option<String>,
     color: Color,
     resources: PlayerResources,
+    pieces: PieceInventory,
     development_cards: DevelopmentCards,
+    played_development_cards: PlayedDevelopmentCards,
+    last_played_development_card_turn: Option<usize>,
+}
+
+impl HeldDevelopmentCard {
+    pub fn get_card(&self) -> &DevelopmentCard {
+        &self.card
+    }
+
+    pub fn get_acquired_turn(&self) -> &usize {
+        &self.acquired_turn
+    }
 }
 
 impl Player {
+    pub fn from(id: PlayerId, color: Color, pieces: PieceInventory) -> Self {
+        Player {
+            id,
+            user_id: None,
+            color,
+            resources: PlayerResources::new(),
+            pieces,
+            development_cards: vec![],
+            played_development_cards: BTreeMap::new(),
+            last_played_development_card_turn: None,
+        }
+    }
+
     pub fn get_id(&self) -> &PlayerId {
         &self.id
     }
 
+    pub fn get_color(&self) -> &Color {
+        &self.color
+    }
+
+    /// Server user controlling the player
+    pub fn get_user_id(&self) -> &Option<String> {
+        &self.user_id
+    }
+
+    pub fn set_user_id(&mut self, user_id: Option<String>) {
+        self.user_id = user_id;
+    }
+
     pub fn get_resources(&self) -> &PlayerResources {
         &self.resources
     }
@@ -29,6 +78,14 @@ impl Player {
         &mut self.resources
     }
 
+    pub fn get_pieces(&self) -> &PieceInventory {
+        &self.pieces
+    }
+
+    pub fn get_pieces_mut(&mut self) -> &mut PieceInventory {
+        &mut self.pieces
+    }
+
     pub fn get_development_cards(&self) -> &DevelopmentCards {
         &self.development_cards
     }
@@ -36,4 +93,55 @@ impl Player {
     pub fn get_development_cards_mut(&mut self) -> &mut DevelopmentCards {
         &mut self.development_cards
     }
+
+    pub fn add_development_card(&mut self, card: DevelopmentCard, turn: usize) {
+        self.development_cards.push(HeldDevelopmentCard {
+            card,
+            acquired_turn: turn,
+        });
+    }
+
+    pub fn get_development_card_count(&self, card: DevelopmentCard) -> usize {
+        self.development_cards
+            .iter()
+            .filter(|held_card| held_card.card == card)
+            .count()
+    }
+
+    /// Cards can be played earliest in the turn after they were acquired
+    pub fn get_playable_development_card_count(&self, card: DevelopmentCard, turn: usize) -> usize {
+        self.development_cards
+            .iter()
+            .filter(|held_card| held_card.card == card && held_card.acquired_turn < turn)
+            .count()
+    }
+
+    pub fn get_played_development_cards(&self) -> &PlayedDevelopmentCards {
+        &self.played_development_cards
+    }
+
+    pub fn get_played_development_card_count(&self, card: DevelopmentCard) -> usize {
+        *self.played_development_cards.get(&card).unwrap_or(&0)
+    }
+
+    pub fn has_played_development_card_in_turn(&self, turn: usize) -> bool {
+        self.last_played_development_card_turn == Some(turn)
+    }
+
+    /// Moves a playable card to the played cards
+    pub fn play_development_card(&mut self, card: DevelopmentCard, turn: usize) -> bool {
+        let card_index = match self
+            .development_cards
+            .iter()
+            .position(|held_card| held_card.card == card && held_card.acquired_turn < turn)
+        {
+            None => return false,
+            Some(index) => index,
+        };
+
+        self.development_cards.remove(card_index);
+        *self.played_development_cards.entry(card).or_insert(0) += 1;
+        self.last_played_development_card_turn = Some(turn);
+        true
+    }
 }
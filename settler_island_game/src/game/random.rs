@@ -0,0 +1,31 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+/// Random number generator of the game, its state can be saved with serde
+pub type GameRng = ChaCha12Rng;
+
+/// Independent random number streams derived from the game seed.
+/// Every part of the game draws from its own stream, so the same seed and
+/// the same actions always reproduce the same game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomStream {
+    BoardLayout,
+    DevelopmentCards,
+    Dice,
+    Robbery,
+}
+
+impl RandomStream {
+    pub fn create_rng(&self, seed: u64) -> GameRng {
+        GameRng::seed_from_u64(seed ^ self.get_salt())
+    }
+
+    fn get_salt(&self) -> u64 {
+        match self {
+            RandomStream::BoardLayout => 0x9e37_79b9_7f4a_7c15,
+            RandomStream::DevelopmentCards => 0xbf58_476d_1ce4_e5b9,
+            RandomStream::Dice => 0x94d0_49bb_1331_11eb,
+            RandomStream::Robbery => 0xd6e8_feb8_6659_fd93,
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    color::Color,
+    player::{Player, PlayerId},
+    state::action_data::GameActionKind,
+    Game, GameError, GameSettings,
+};
+
+/// Replays with another version cannot be read
+pub const REPLAY_VERSION: u32 = 1;
+
+/// First line of a replay file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub settings: GameSettings,
+    pub players: Vec<ReplayPlayer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayPlayer {
+    pub player_id: PlayerId,
+    pub user_id: Option<String>,
+    pub color: Color,
+}
+
+/// Action performed by a player and the error it failed with.
+/// Failed actions are only kept if they advanced the random number streams
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedAction {
+    pub player_id: PlayerId,
+    pub action: GameActionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<GameError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    InvalidFormat(String),
+    UnsupportedVersion(u32),
+    GameCreationFailed(String),
+    /// The action at `step` did not end with the recorded result
+    ResultMismatch {
+        step: usize,
+        expected: Option<GameError>,
+        actual: Option<GameError>,
+    },
+}
+
+/// Settings, players and every action of a game.
+/// Serialized as JSON Lines with the header in the first line and one action per following line
+#[derive(Debug, Clone)]
+pub struct Replay {
+    header: ReplayHeader,
+    actions: Vec<RecordedAction>,
+}
+
+impl Replay {
+    pub fn from_game(game: &Game) -> Self {
+        Replay {
+            header: ReplayHeader {
+                version: REPLAY_VERSION,
+                settings: game.get_settings().clone(),
+                players: game
+                    .get_players()
+                    .iter()
+                    .map(|player| {
+                        let player = player.borrow();
+                        ReplayPlayer {
+                            player_id: *player.get_id(),
+                            user_id: player.get_user_id().clone(),
+                            color: *player.get_color(),
+                        }
+                    })
+                    .collect(),
+            },
+            actions: game.get_recorded_actions().clone(),
+        }
+    }
+
+    pub fn from_json_lines(replay_lines: &str) -> Result<Self, ReplayError> {
+        let mut lines = replay_lines.lines().filter(|line| !line.trim().is_empty());
+        let header: ReplayHeader = match lines.next() {
+            None => return Err(ReplayError::InvalidFormat("Replay is empty".to_string())),
+            Some(line) => match serde_json::from_str(line) {
+                Err(err) => return Err(ReplayError::InvalidFormat(err.to_string())),
+                Ok(header) => header,
+            },
+        };
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+
+        let mut actions = vec![];
+        for (line_index, line) in lines.enumerate() {
+            match serde_json::from_str(line) {
+                Err(err) => {
+                    return Err(ReplayError::InvalidFormat(format!(
+                        "Action {} is invalid \"{}\"",
+                        line_index, err
+                    )))
+                }
+                Ok(action) => actions.push(action),
+            }
+        }
+
+        Ok(Replay { header, actions })
+    }
+
+    pub fn to_json_lines(&self) -> Result<String, ReplayError> {
+        let mut lines = vec![];
+        match serde_json::to_string(&self.header) {
+            Err(err) => return Err(ReplayError::InvalidFormat(err.to_string())),
+            Ok(line) => lines.push(line),
+        }
+        for action in &self.actions {
+            match serde_json::to_string(action) {
+                Err(err) => return Err(ReplayError::InvalidFormat(err.to_string())),
+                Ok(line) => lines.push(line),
+            }
+        }
+        Ok(lines.join("\n") + "\n")
+    }
+
+    pub fn get_header(&self) -> &ReplayHeader {
+        &self.header
+    }
+
+    pub fn get_actions(&self) -> &Vec<RecordedAction> {
+        &self.actions
+    }
+
+    /// Recreates the game before the first action
+    pub fn create_game(&self) -> Result<Game, ReplayError> {
+        let players = self
+            .header
+            .players
+            .iter()
+            .map(|replay_player| {
+                let mut player = Player::from(
+                    replay_player.player_id,
+                    replay_player.color,
+                    self.header.settings.pieces,
+                );
+                player.set_user_id(replay_player.user_id.clone());
+                player
+            })
+            .collect();
+
+        match Game::generate(self.header.settings.clone(), players) {
+            Err(err) => Err(ReplayError::GameCreationFailed(err)),
+            Ok(game) => Ok(game),
+        }
+    }
+
+    /// Re-runs the first `step_count` actions, all actions if `step_count` is `None`
+    pub fn run(&self, step_count: Option<usize>) -> Result<Game, ReplayError> {
+        let mut runner = ReplayRunner::from(self)?;
+        runner.run_to(step_count.unwrap_or(self.actions.len()))?;
+        Ok(runner.game)
+    }
+}
+
+/// Re-runs a replay step by step
+pub struct ReplayRunner<'a> {
+    replay: &'a Replay,
+    game: Game,
+    step: usize,
+}
+
+impl<'a> ReplayRunner<'a> {
+    pub fn from(replay: &'a Replay) -> Result<Self, ReplayError> {
+        Ok(ReplayRunner {
+            replay,
+            game: replay.create_game()?,
+            step: 0,
+        })
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Number of actions which were performed
+    pub fn get_step(&self) -> usize {
+        self.step
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= self.replay.actions.len()
+    }
+
+    /// Performs the next action, returns `false` if no actions are left
+    pub fn next_step(&mut self) -> Result<bool, ReplayError> {
+        let recorded_action = match self.replay.actions.get(self.step) {
+            None => return Ok(false),
+            Some(recorded_action) => recorded_action,
+        };
+
+        let actual = self
+            .game
+            .perform_action(&recorded_action.player_id, recorded_action.action.clone())
+            .err();
+        if actual != recorded_action.error {
+            return Err(ReplayError::ResultMismatch {
+                step: self.step,
+                expected: recorded_action.error,
+                actual,
+            });
+        }
+
+        self.step += 1;
+        Ok(true)
+    }
+
+    /// Performs actions until `step` actions were performed or no actions are left
+    pub fn run_to(&mut self, step: usize) -> Result<(), ReplayError> {
+        while self.step < step {
+            if !self.next_step()? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        snapshot::GameSnapshot,
+        state::action_data::GameActionKind,
+        test_util::{generate_game, get_next_action, play_until},
+        Game, GameError,
+    };
+
+    use super::{Replay, ReplayError, ReplayRunner, REPLAY_VERSION};
+
+    fn assert_same_game(game_a: &Game, game_b: &Game) {
+        for player_id in 0..game_a.get_player_count() {
+            assert_eq!(
+                serde_json::to_string(&GameSnapshot::for_player(game_a, &player_id)).unwrap(),
+                serde_json::to_string(&GameSnapshot::for_player(game_b, &player_id)).unwrap()
+            );
+        }
+        assert_eq!(
+            serde_json::to_string(game_a.get_events()).unwrap(),
+            serde_json::to_string(game_b.get_events()).unwrap()
+        );
+    }
+
+    #[test]
+    fn replay_round_trip_recreates_game() {
+        let mut game = generate_game(3, 5);
+        play_until(&mut game, 150, |_| false);
+        let off_turn_player_id = (*game.get_current_player_index() + 1) % 3;
+        let recorded_action_count = game.get_recorded_actions().len();
+        assert_eq!(
+            game.perform_action(&off_turn_player_id, GameActionKind::EndTurn),
+            Err(GameError::NotPlayerTurn)
+        );
+        // rejected before any random number was drawn, replaying it is not needed
+        assert_eq!(game.get_recorded_actions().len(), recorded_action_count);
+        play_until(&mut game, 150, |_| false);
+
+        let replay_lines = Replay::from_game(&game).to_json_lines().unwrap();
+        let replay = Replay::from_json_lines(&replay_lines).unwrap();
+
+        assert_eq!(
+            replay.get_actions().len(),
+            game.get_recorded_actions().len()
+        );
+        assert_same_game(&game, &replay.run(None).unwrap());
+    }
+
+    #[test]
+    fn replay_runner_stops_at_step() {
+        let mut game = generate_game(2, 5);
+        play_until(&mut game, 40, |_| false);
+        let mut partial_game = generate_game(2, 5);
+        play_until(&mut partial_game, 20, |_| false);
+
+        let replay = Replay::from_game(&game);
+        let mut runner = ReplayRunner::from(&replay).unwrap();
+        runner.run_to(20).unwrap();
+
+        assert_eq!(runner.get_step(), 20);
+        assert!(!runner.is_finished());
+        assert_same_game(&partial_game, runner.get_game());
+    }
+
+    #[test]
+    fn replay_rejects_other_version() {
+        let game = generate_game(2, 5);
+        let replay_lines = Replay::from_game(&game).to_json_lines().unwrap().replacen(
+            &format!("\"version\":{}", REPLAY_VERSION),
+            "\"version\":0",
+            1,
+        );
+
+        assert_eq!(
+            Replay::from_json_lines(&replay_lines).unwrap_err(),
+            ReplayError::UnsupportedVersion(0)
+        );
+    }
+
+    #[test]
+    fn replay_detects_diverging_results() {
+        let mut game = generate_game(2, 5);
+        let (player_id, action) = get_next_action(&game).unwrap();
+        game.perform_action(&player_id, action).unwrap();
+
+        let replay_lines = Replay::from_game(&game).to_json_lines().unwrap();
+        let tampered_lines =
+            replay_lines.replacen(r#""player_id":0,"action""#, r#""player_id":1,"action""#, 1);
+        let replay = Replay::from_json_lines(&tampered_lines).unwrap();
+
+        assert!(matches!(
+            replay.run(None),
+            Err(ReplayError::ResultMismatch { step: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::{DoubleDiceRoll, SavedBoard},
+    event::GameEvent,
+    player::{Player, PlayerId},
+    replay::RecordedAction,
+    state::state_machine::SavedStateMachine,
+    title::BonusTitle,
+    trade::TradeOffer,
+    Game, GameSettings,
+};
+
+/// Saves with another version cannot be restored
+pub const SAVE_VERSION: u32 = 1;
+
+/// Everything needed to continue a game after a server restart.
+/// The static board layout is not saved, it is generated again from the seed of the settings
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedGame {
+    version: u32,
+    settings: GameSettings,
+    board: SavedBoard,
+    state_machine: SavedStateMachine,
+    current_player_index: usize,
+    turn: usize,
+    dice_roll: Option<DoubleDiceRoll>,
+    players: Vec<Player>,
+    trade_offer: Option<TradeOffer>,
+    title_holders: BTreeMap<BonusTitle, PlayerId>,
+    events: Vec<GameEvent>,
+    recorded_actions: Vec<RecordedAction>,
+}
+
+impl SavedGame {
+    pub fn from(game: &Game) -> Self {
+        SavedGame {
+            version: SAVE_VERSION,
+            settings: game.settings.clone(),
+            board: game.board.get_saved_board(),
+            state_machine: game.state_machine.borrow().get_saved_state_machine(),
+            current_player_index: game.current_player_index,
+            turn: game.turn,
+            dice_roll: game.dice_roll,
+            players: game
+                .players
+                .iter()
+                .map(|player| player.borrow().clone())
+                .collect(),
+            trade_offer: game.active_trade_offer.borrow().clone(),
+            title_holders: game.title_holders.clone(),
+            events: game.events.clone(),
+            recorded_actions: game.recorded_actions.clone(),
+        }
+    }
+
+    pub fn get_version(&self) -> &u32 {
+        &self.version
+    }
+
+    pub fn get_settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    /// Generates the game from the saved settings and applies the saved progress
+    pub fn restore(self) -> Result<Game, String> {
+        if self.version != SAVE_VERSION {
+            return Err(format!("Save version {} is not supported", self.version));
+        }
+        if !self.players.is_empty() && self.current_player_index >= self.players.len() {
+            return Err(format!(
+                "Current player index {} is out of range",
+                self.current_player_index
+            ));
+        }
+
+        let mut game = Game::generate(self.settings, self.players)?;
+        game.board.restore_saved_board(self.board)?;
+        game.state_machine
+            .borrow_mut()
+            .restore_saved_state_machine(self.state_machine)?;
+        game.current_player_index = self.current_player_index;
+        game.turn = self.turn;
+        game.dice_roll = self.dice_roll;
+        game.active_trade_offer.replace(self.trade_offer);
+        game.title_holders = self.title_holders;
+        game.events = self.events;
+        game.recorded_actions = self.recorded_actions;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        snapshot::GameSnapshot,
+        test_util::{generate_game, get_next_action, play_until},
+        Game,
+    };
+
+    use super::{SavedGame, SAVE_VERSION};
+
+    fn save_and_restore(game: &Game) -> Game {
+        let save_json = serde_json::to_string(&SavedGame::from(game)).unwrap();
+        serde_json::from_str::<SavedGame>(&save_json)
+            .unwrap()
+            .restore()
+            .unwrap()
+    }
+
+    fn assert_same_game(game_a: &Game, game_b: &Game) {
+        assert_eq!(
+            serde_json::to_string(&SavedGame::from(game_a)).unwrap(),
+            serde_json::to_string(&SavedGame::from(game_b)).unwrap()
+        );
+        for player_id in 0..game_a.get_player_count() {
+            assert_eq!(
+                serde_json::to_string(&GameSnapshot::for_player(game_a, &player_id)).unwrap(),
+                serde_json::to_string(&GameSnapshot::for_player(game_b, &player_id)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn restored_game_equals_saved_game() {
+        let mut game = generate_game(3, 9);
+        play_until(&mut game, 120, |_| false);
+
+        assert_same_game(&game, &save_and_restore(&game));
+    }
+
+    #[test]
+    fn restored_game_continues_like_saved_game() {
+        let mut game = generate_game(3, 9);
+        play_until(&mut game, 120, |_| false);
+        let mut restored_game = save_and_restore(&game);
+
+        // dice rolls and robberies continue with the same random numbers
+        for _ in 0..200 {
+            let (player_id, action) = match get_next_action(&game) {
+                None => break,
+                Some(next_action) => next_action,
+            };
+            assert_eq!(
+                game.perform_action(&player_id, action.clone()),
+                restored_game.perform_action(&player_id, action)
+            );
+        }
+        assert_same_game(&game, &restored_game);
+    }
+
+    #[test]
+    fn restore_rejects_other_version() {
+        let game = generate_game(2, 9);
+        let mut saved_game = SavedGame::from(&game);
+        saved_game.version = SAVE_VERSION + 1;
+
+        assert!(saved_game.restore().is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    board::location::settlement_location::SettlementType, player::PlayerId,
+    state::states::development_card::DevelopmentCard, title::BonusTitle, Game,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerScore {
+    player_id: PlayerId,
+    settlement_points: usize,
+    development_card_points: usize,
+    titles: Vec<BonusTitle>,
+}
+
+impl PlayerScore {
+    pub fn from(game: &Game, player_id: &PlayerId) -> Self {
+        PlayerScore {
+            player_id: *player_id,
+            settlement_points: get_settlement_points(game, player_id),
+            development_card_points: get_development_card_points(game, player_id),
+            titles: game.get_player_titles(player_id),
+        }
+    }
+
+    pub fn get_player_id(&self) -> &PlayerId {
+        &self.player_id
+    }
+
+    pub fn get_titles(&self) -> &Vec<BonusTitle> {
+        &self.titles
+    }
+
+    /// Victory points which are visible to every player, hidden victory point cards are excluded
+    pub fn get_public_total(&self) -> usize {
+        self.get_total() - self.development_card_points
+    }
+
+    pub fn get_total(&self) -> usize {
+        self.settlement_points
+            + self.development_card_points
+            + self
+                .titles
+                .iter()
+                .map(|title| title.get_victory_points())
+                .sum::<usize>()
+    }
+}
+
+/// Scores of all players ordered from highest to lowest total
+pub fn get_standings(game: &Game) -> Vec<PlayerScore> {
+    let mut standings: Vec<PlayerScore> = game
+        .get_players()
+        .iter()
+        .map(|player| PlayerScore::from(game, player.borrow().get_id()))
+        .collect();
+    standings.sort_by_key(|score| Reverse(score.get_total()));
+    standings
+}
+
+fn get_settlement_points(game: &Game, player_id: &PlayerId) -> usize {
+    game.get_board()
+        .get_settlement_map()
+        .get_player_settlements(player_id)
+        .iter()
+        .filter_map(|location| location.get_settlement().as_ref())
+        .map(|settlement| match settlement.get_settlement_type() {
+            SettlementType::Village => 1,
+            SettlementType::City => 2,
+        })
+        .sum()
+}
+
+fn get_development_card_points(game: &Game, player_id: &PlayerId) -> usize {
+    game.get_player(*player_id)
+        .borrow()
+        .get_development_card_count(DevelopmentCard::VictoryPoint)
+}
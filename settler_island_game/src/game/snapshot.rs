@@ -0,0 +1,373 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{
+    board::{
+        hexagon::{cube_coordinates::CubeCoordinates, hexagon_tile::TileType},
+        location::{
+            dice_chip_location::DiceChipLocation,
+            road_location::{RoadLocation, RoadLocationId},
+            robber_location::RobberLocation,
+            seaport_location::SeaportLocation,
+            settlement_location::{PlayerSettlement, SettlementLocation, SettlementLocationId},
+        },
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
+        DoubleDiceRoll, GameBoard,
+    },
+    color::Color,
+    piece::PieceInventory,
+    player::{DevelopmentCards, PlayedDevelopmentCards, Player, PlayerId},
+    score::PlayerScore,
+    title::BonusTitle,
+    Game,
+};
+
+/// Serializable copy of everything a client needs to render the game.
+/// Hidden information is only included in the hand of the viewing player,
+/// the order of the development card deck and the board RNG are never included
+#[derive(Serialize, Debug, Clone)]
+pub struct GameSnapshot {
+    pub turn: usize,
+    pub current_player_id: Option<PlayerId>,
+    pub dice_roll: Option<DoubleDiceRoll>,
+    pub victory_points_to_win: usize,
+    pub state_id: String,
+    /// Data of the current state, e.g. the remaining free roads
+    pub state: Option<Value>,
+    pub board: BoardSnapshot,
+    pub players: Vec<PlayerSnapshot>,
+    /// Hand of the viewing player, `None` for spectators
+    pub hand: Option<PlayerHandSnapshot>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BoardSnapshot {
+    pub tiles: Vec<TileSnapshot>,
+    pub dice_chips: Vec<DiceChipLocation>,
+    pub robber: RobberLocation,
+    pub settlements: Vec<SettlementSnapshot>,
+    pub roads: Vec<RoadSnapshot>,
+    pub bank: ResourceCollection,
+    pub development_cards_left: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TileSnapshot {
+    pub coordinates: CubeCoordinates,
+    pub tile_type: TileType,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SettlementSnapshot {
+    pub id: SettlementLocationId,
+    pub neighbor_tiles: Vec<CubeCoordinates>,
+    pub settlement: Option<PlayerSettlement>,
+    pub seaport: Option<SeaportSnapshot>,
+}
+
+/// Exchange rate of a seaport, `resource` is `None` for seaports accepting any resource
+#[derive(Serialize, Debug, Clone)]
+pub struct SeaportSnapshot {
+    pub receive_count: usize,
+    pub send_count: usize,
+    pub resource: Option<Resource>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RoadSnapshot {
+    pub id: RoadLocationId,
+    pub settlement_a_id: SettlementLocationId,
+    pub settlement_b_id: SettlementLocationId,
+    pub player_id: Option<PlayerId>,
+}
+
+/// Information about a player which is visible to every other player
+#[derive(Serialize, Debug, Clone)]
+pub struct PlayerSnapshot {
+    pub player_id: PlayerId,
+    pub color: Color,
+    pub resource_count: usize,
+    pub development_card_count: usize,
+    pub played_development_cards: PlayedDevelopmentCards,
+    pub pieces: PieceInventory,
+    pub titles: Vec<BonusTitle>,
+    pub victory_points: usize,
+}
+
+/// Cards and score of the viewing player which are hidden from the opponents
+#[derive(Serialize, Debug, Clone)]
+pub struct PlayerHandSnapshot {
+    pub player_id: PlayerId,
+    pub resources: ResourceCollection,
+    pub development_cards: DevelopmentCards,
+    pub score: PlayerScore,
+    pub victory_points: usize,
+}
+
+impl GameSnapshot {
+    /// View of a player who only sees the own hand and card counts of the opponents
+    pub fn for_player(game: &Game, player_id: &PlayerId) -> Self {
+        let mut snapshot = GameSnapshot::for_spectator(game);
+        snapshot.hand = game
+            .get_players()
+            .get(*player_id)
+            .map(|player| PlayerHandSnapshot::from(game, &player.borrow()));
+        snapshot
+    }
+
+    /// View without any hidden information
+    pub fn for_spectator(game: &Game) -> Self {
+        let state_machine = game.get_state_machine().borrow();
+        GameSnapshot {
+            turn: game.get_turn(),
+            current_player_id: match game.get_players().is_empty() {
+                true => None,
+                false => Some(*game.get_current_player().borrow().get_id()),
+            },
+            dice_roll: *game.get_dice_roll(),
+            victory_points_to_win: game.get_settings().victory_points_to_win,
+            state_id: state_machine.get_current_state_id().clone(),
+            state: state_machine.get_current_state_data(game),
+            board: BoardSnapshot::from(game.get_board()),
+            players: game
+                .get_players()
+                .iter()
+                .map(|player| PlayerSnapshot::from(game, &player.borrow()))
+                .collect(),
+            hand: None,
+        }
+    }
+}
+
+impl BoardSnapshot {
+    pub fn from(board: &GameBoard) -> Self {
+        let mut tiles: Vec<TileSnapshot> = board
+            .get_tile_map()
+            .get_tiles()
+            .into_iter()
+            .map(|tile| {
+                let tile = tile.borrow();
+                TileSnapshot {
+                    coordinates: *tile.get_coordinates(),
+                    tile_type: *tile.get_type(),
+                }
+            })
+            .collect();
+        tiles.sort_by_key(|tile| (tile.coordinates.q, tile.coordinates.r));
+
+        let settlement_map = board.get_settlement_map();
+        let mut settlements: Vec<SettlementSnapshot> = settlement_map
+            .get_settlements()
+            .into_iter()
+            .map(SettlementSnapshot::from)
+            .collect();
+        settlements.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut roads: Vec<RoadSnapshot> = settlement_map
+            .get_roads()
+            .into_iter()
+            .map(RoadSnapshot::from)
+            .collect();
+        roads.sort_by(|a, b| a.id.cmp(&b.id));
+
+        BoardSnapshot {
+            tiles,
+            dice_chips: board.get_dice_chips().clone(),
+            robber: *board.get_robber(),
+            settlements,
+            roads,
+            bank: board.get_bank().get_resources().get_resources(),
+            development_cards_left: board.get_development_cards_left(),
+        }
+    }
+}
+
+impl SettlementSnapshot {
+    pub fn from(settlement: &SettlementLocation) -> Self {
+        SettlementSnapshot {
+            id: settlement.get_id().clone(),
+            neighbor_tiles: settlement.get_neighbor_tiles().clone(),
+            settlement: settlement.get_settlement().clone(),
+            seaport: settlement.get_seaport().as_ref().map(SeaportSnapshot::from),
+        }
+    }
+}
+
+impl SeaportSnapshot {
+    pub fn from(seaport: &SeaportLocation) -> Self {
+        let trade_contract = seaport.get_trade_contract();
+        SeaportSnapshot {
+            receive_count: trade_contract.get_receive_count(),
+            send_count: trade_contract.get_send_count(),
+            resource: trade_contract.get_resource(),
+        }
+    }
+}
+
+impl RoadSnapshot {
+    pub fn from(road: &RoadLocation) -> Self {
+        RoadSnapshot {
+            id: road.get_id().clone(),
+            settlement_a_id: road.get_settlement_a_id().clone(),
+            settlement_b_id: road.get_settlement_b_id().clone(),
+            player_id: road
+                .get_player_road()
+                .as_ref()
+                .map(|player_road| *player_road.get_player_id()),
+        }
+    }
+}
+
+impl PlayerSnapshot {
+    pub fn from(game: &Game, player: &Player) -> Self {
+        let score = PlayerScore::from(game, player.get_id());
+        PlayerSnapshot {
+            player_id: *player.get_id(),
+            color: *player.get_color(),
+            resource_count: player.get_resources().get_total_resources(),
+            development_card_count: player.get_development_cards().len(),
+            played_development_cards: player.get_played_development_cards().clone(),
+            pieces: *player.get_pieces(),
+            titles: score.get_titles().clone(),
+            victory_points: score.get_public_total(),
+        }
+    }
+}
+
+impl PlayerHandSnapshot {
+    pub fn from(game: &Game, player: &Player) -> Self {
+        let score = PlayerScore::from(game, player.get_id());
+        PlayerHandSnapshot {
+            player_id: *player.get_id(),
+            resources: player.get_resources().get_resources(),
+            development_cards: player.get_development_cards().clone(),
+            victory_points: score.get_total(),
+            score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::game::{
+        board::resource::player_resources::ResourceCollection,
+        state::{
+            action_data::{GameActionKind, TradeOfferData},
+            states::development_card::DevelopmentCard,
+        },
+        test_util::{generate_game, get_next_action, play_until},
+        title::BonusTitle,
+        Game,
+    };
+
+    use super::GameSnapshot;
+
+    /// Resource the current player can offer and a different one the next player can pay
+    fn get_trade_resources(game: &Game) -> Option<(ResourceCollection, ResourceCollection)> {
+        let creator_id = *game.get_current_player_index();
+        let partner_id = (creator_id + 1) % game.get_player_count();
+        let creator_resources = game
+            .get_player(creator_id)
+            .borrow()
+            .get_resources()
+            .get_resources();
+        let partner_resources = game
+            .get_player(partner_id)
+            .borrow()
+            .get_resources()
+            .get_resources();
+
+        let (offered_resource, _) = creator_resources.iter().find(|(_, count)| *count > 0)?;
+        let (received_resource, _) = partner_resources
+            .iter()
+            .find(|(resource, count)| *count > 0 && *resource != offered_resource)?;
+        Some((
+            ResourceCollection::from([(offered_resource, 1)]),
+            ResourceCollection::from([(received_resource, 1)]),
+        ))
+    }
+
+    #[test]
+    fn snapshot_contains_active_trade_offer() {
+        let mut game = generate_game(2, 7);
+        assert!(play_until(&mut game, 500, |game| {
+            game.get_state_machine().borrow().get_current_state_id() == "SelectAction"
+                && get_trade_resources(game).is_some()
+        }));
+        let creator_id = *game.get_current_player_index();
+        let partner_id = (creator_id + 1) % 2;
+        let (resource_offer, resource_receive) = get_trade_resources(&game).unwrap();
+
+        game.perform_action(
+            &creator_id,
+            GameActionKind::OfferTrade(TradeOfferData {
+                resource_offer,
+                resource_receive,
+            }),
+        )
+        .unwrap();
+        game.perform_action(&partner_id, GameActionKind::AcceptTrade)
+            .unwrap();
+
+        let snapshot = serde_json::to_value(GameSnapshot::for_spectator(&game)).unwrap();
+        assert_eq!(snapshot["state_id"], json!("Trading"));
+        assert_eq!(
+            snapshot["state"]["trade_offer"]["creator"],
+            json!(creator_id)
+        );
+        assert_eq!(
+            snapshot["state"]["trade_offer"]["players_accepted"][partner_id.to_string()],
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn snapshot_json_is_deterministic() {
+        let mut game_a = generate_game(3, 11);
+        let mut game_b = generate_game(3, 11);
+        for _ in 0..300 {
+            let (player_id, action) = match get_next_action(&game_a) {
+                None => break,
+                Some(next_action) => next_action,
+            };
+            game_a.perform_action(&player_id, action.clone()).unwrap();
+            game_b.perform_action(&player_id, action).unwrap();
+        }
+
+        for player_id in 0..3 {
+            assert_eq!(
+                serde_json::to_string(&GameSnapshot::for_player(&game_a, &player_id)).unwrap(),
+                serde_json::to_string(&GameSnapshot::for_player(&game_b, &player_id)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn snapshot_lists_titles_and_played_cards_in_order() {
+        let mut game = generate_game(2, 7);
+        game.set_title_holder(BonusTitle::LargestArmy, Some(0));
+        game.set_title_holder(BonusTitle::LongestRoad, Some(0));
+        {
+            let mut player = game.get_player(0).borrow_mut();
+            for card in [
+                DevelopmentCard::VictoryPoint,
+                DevelopmentCard::Knight,
+                DevelopmentCard::Invention,
+                DevelopmentCard::Monopoly,
+                DevelopmentCard::StreetConstruction,
+            ] {
+                player.add_development_card(card, 0);
+                player.play_development_card(card, 1);
+            }
+        }
+
+        // the JSON map type sorts keys on its own, only the string shows the serialized order
+        let snapshot = serde_json::to_string(&GameSnapshot::for_spectator(&game)).unwrap();
+        assert!(snapshot.contains(
+            r#""played_development_cards":{"StreetConstruction":1,"Monopoly":1,"Invention":1,"Knight":1,"VictoryPoint":1}"#
+        ));
+        assert!(snapshot.contains(r#""titles":["LongestRoad","LargestArmy"]"#));
+    }
+}
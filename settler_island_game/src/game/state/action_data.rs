@@ -1,60 +1,106 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use strum::IntoStaticStr;
 
 use crate::game::{
     board::{
-        hexagon::cube_coordinates::CubeCoordinates, location::settlement_location::SettlementType,
-        resource::player_resources::ResourceCollection,
+        hexagon::cube_coordinates::CubeCoordinates,
+        location::settlement_location::SettlementType,
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
     },
     player::PlayerId,
 };
 
 use super::states::development_card::DevelopmentCard;
 
-pub const DRAW_DEVELOPMENT_CARD_ACTION: &str = "BuildSettlement";
-pub const BUILD_SETTLEMENT_ACTION: &str = "BuildSettlement";
-pub const BUILD_ROAD_ACTION: &str = "BuildRoad";
-pub const PLACE_ROBBER_ACTION: &str = "PlaceRobber";
-pub const REMOVE_CARDS_ACTION: &str = "RemoveCards";
-pub const SELECT_SETTLEMENT_ACTION: &str = "SelectSettlement";
-pub const ROLL_DICE_ACTION: &str = "RollDice";
-pub const END_TURN_ACTION: &str = "EndTurn";
-
-pub const OFFER_TRADE_ACTION: &str = "OfferTrade";
-pub const OFFER_BANK_TRADE_ACTION: &str = "OfferBankTrade";
-pub const ACCEPT_TRADE_ACTION: &str = "AcceptTrade";
-pub const REJECT_TRADE_ACTION: &str = "RejectTrade";
-pub const COMPLETE_TRADE_ACTION: &str = "CompleteTrade";
-pub const CANCEL_TRADE_ACTION: &str = "CancelTrade";
-
-#[derive(Debug, Deserialize, Clone)]
+/// Every action a player can perform.
+/// Actions are serialized as `{"id": "BuildRoad", "data": {"road_id": "..."}}`,
+/// actions without payload omit `data`
+#[derive(Debug, Clone, Deserialize, Serialize, IntoStaticStr)]
+#[serde(tag = "id", content = "data")]
+pub enum GameActionKind {
+    BuyDevelopmentCard,
+    PlayDevelopmentCard(PlayDevelopmentCardData),
+    BuildSettlement(PlaceSettlementData),
+    BuildRoad(PlaceRoadData),
+    PlaceRobber(PlaceRobberData),
+    RemoveCards(ResourceCollection),
+    RollDice,
+    EndTurn,
+
+    OfferTrade(TradeOfferData),
+    OfferBankTrade(TradeOfferData),
+    AcceptTrade,
+    RejectTrade,
+    CompleteTrade(CompletePlayerTradeData),
+    CancelTrade,
+}
+
+impl GameActionKind {
+    /// Serialized `id` of the action
+    pub fn get_id(&self) -> &'static str {
+        self.into()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PlaceSettlementData {
     pub settlement_type: SettlementType,
     pub settlement_id: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PlaceRoadData {
     pub road_id: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PlaceRobberData {
     pub tile_location: CubeCoordinates,
-    pub robbed_player_id: PlayerId,
+    #[serde(default)]
+    pub robbed_player_id: Option<PlayerId>,
+}
+
+/// Serialized as `{"card": "Monopoly", "data": {"resource": "Ore"}}`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "card", content = "data")]
+pub enum PlayDevelopmentCardData {
+    StreetConstruction,
+    Monopoly(MonopolyData),
+    Invention(InventionData),
+    Knight,
+    VictoryPoint,
+}
+
+impl PlayDevelopmentCardData {
+    pub fn get_card(&self) -> DevelopmentCard {
+        match self {
+            PlayDevelopmentCardData::StreetConstruction => DevelopmentCard::StreetConstruction,
+            PlayDevelopmentCardData::Monopoly(_) => DevelopmentCard::Monopoly,
+            PlayDevelopmentCardData::Invention(_) => DevelopmentCard::Invention,
+            PlayDevelopmentCardData::Knight => DevelopmentCard::Knight,
+            PlayDevelopmentCardData::VictoryPoint => DevelopmentCard::VictoryPoint,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InventionData {
+    pub resource_a: Resource,
+    pub resource_b: Resource,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct DrawDevelopmentCardData {
-    pub card: String,
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MonopolyData {
+    pub resource: Resource,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TradeOfferData {
     pub resource_offer: ResourceCollection,
     pub resource_receive: ResourceCollection,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CompletePlayerTradeData {
     pub accepted_player_id: PlayerId,
 }
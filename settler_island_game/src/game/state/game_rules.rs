@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+
+use log::error;
+use settler_island_util::state_id::StateId;
+
+use super::{
+    state_machine::{GameStateT, StateMachine},
+    states::{game_error::GameErrorState, game_over::GameOver, get_base_game_states},
+};
+
+/// States the game can transition to from every state
+pub fn get_global_transition_ids() -> Vec<StateId> {
+    vec![GameOver::get_id(), GameErrorState::get_id()]
+}
+
+/// Builder for the states of a game.
+/// Building checks that every transition of a state leads to a registered state
+/// and that every registered state can be reached from the start state
+pub struct GameRules {
+    states: Vec<GameStateT>,
+    start_state_id: Option<StateId>,
+}
+
+impl GameRules {
+    pub fn new() -> Self {
+        GameRules {
+            states: vec![],
+            start_state_id: None,
+        }
+    }
+
+    /// Rules of the base game starting with the placement of the first village
+    pub fn base() -> Self {
+        get_base_game_states()
+            .into_iter()
+            .fold(GameRules::new(), |rules, state| rules.with_state(state))
+    }
+
+    pub fn with_state(mut self, state: GameStateT) -> Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Defaults to the first added state
+    pub fn with_start_state(mut self, state_id: StateId) -> Self {
+        self.start_state_id = Some(state_id);
+        self
+    }
+
+    pub fn build(self) -> Result<StateMachine, String> {
+        let start_state_id = match self
+            .start_state_id
+            .or_else(|| self.states.first().map(|state| state.get_id()))
+        {
+            None => return Err("Game rules contain no states".to_string()),
+            Some(start_state_id) => start_state_id,
+        };
+
+        let mut state_ids = HashSet::new();
+        for state in &self.states {
+            if !state_ids.insert(state.get_id()) {
+                return Err(format!("State {} is registered twice", state.get_id()));
+            }
+        }
+
+        if !state_ids.contains(start_state_id) {
+            return Err(format!("Start state {} is not registered", start_state_id));
+        }
+
+        for state_id in get_global_transition_ids() {
+            if !state_ids.contains(state_id) {
+                return Err(format!("State {} is not registered", state_id));
+            }
+        }
+
+        for state in &self.states {
+            if let Some(transition_id) = state
+                .get_transition_ids()
+                .into_iter()
+                .find(|transition_id| !state_ids.contains(transition_id))
+            {
+                let err = format!(
+                    "State {} transitions to {} which is not registered",
+                    state.get_id(),
+                    transition_id
+                );
+                error!("{}", err);
+                return Err(err);
+            }
+        }
+
+        let transitions: HashMap<StateId, Vec<StateId>> = self
+            .states
+            .iter()
+            .map(|state| (state.get_id(), state.get_transition_ids()))
+            .collect();
+        let mut reachable_ids: HashSet<StateId> = get_global_transition_ids().into_iter().collect();
+        reachable_ids.insert(start_state_id);
+        let mut pending_ids: Vec<StateId> = reachable_ids.iter().copied().collect();
+        while let Some(state_id) = pending_ids.pop() {
+            for transition_id in &transitions[state_id] {
+                if reachable_ids.insert(transition_id) {
+                    pending_ids.push(transition_id);
+                }
+            }
+        }
+
+        for state in &self.states {
+            if !reachable_ids.contains(state.get_id()) {
+                let err = format!(
+                    "State {} cannot be reached from {}",
+                    state.get_id(),
+                    start_state_id
+                );
+                error!("{}", err);
+                return Err(err);
+            }
+        }
+
+        Ok(StateMachine::from(self.states, start_state_id.to_string()))
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use settler_island_util_derive::HasStateId;
+
+    use crate::game::{
+        player::PlayerId,
+        state::{
+            action_data::GameActionKind,
+            legal_action::LegalAction,
+            state_machine::{GameActionResult, GameState},
+            states::{
+                select_action::SelectAction, start_village_placement::StartVillagePlacement,
+                trading::Trading,
+            },
+        },
+        Game, GameError,
+    };
+
+    use super::GameRules;
+
+    #[derive(HasStateId)]
+    struct Detour {}
+
+    impl GameState for Detour {
+        fn get_state(&self, _game: &Game) -> Option<Value> {
+            None
+        }
+
+        fn activate(&mut self, _game: &Game) {}
+
+        fn perform_action(
+            &mut self,
+            _game: &mut Game,
+            _player_id: &PlayerId,
+            _action: GameActionKind,
+        ) -> GameActionResult {
+            Err(GameError::ActionNotAllowed)
+        }
+
+        fn get_legal_actions(&self, _game: &Game, _player_id: &PlayerId) -> Vec<LegalAction> {
+            vec![]
+        }
+
+        fn get_transition_ids(&self) -> Vec<StateId> {
+            vec![Trading::get_id()]
+        }
+    }
+
+    #[test]
+    fn base_rules_build() {
+        assert!(GameRules::base().build().is_ok());
+    }
+
+    #[test]
+    fn unreachable_state_is_rejected() {
+        let rules = GameRules::base().with_state(Box::new(Detour {}));
+
+        let err = rules.build().err().unwrap();
+
+        assert_eq!(
+            err,
+            format!(
+                "State {} cannot be reached from {}",
+                Detour::get_id(),
+                StartVillagePlacement::get_id()
+            )
+        );
+    }
+
+    #[test]
+    fn transition_to_unregistered_state_is_rejected() {
+        let rules = GameRules::new()
+            .with_state(Box::new(SelectAction::new()))
+            .with_state(Box::new(Trading::new()));
+
+        assert!(rules.build().is_err());
+    }
+}
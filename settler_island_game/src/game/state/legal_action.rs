@@ -0,0 +1,67 @@
+use serde::Serialize;
+
+use crate::game::{
+    board::{
+        hexagon::cube_coordinates::CubeCoordinates,
+        location::{
+            road_location::RoadLocationId,
+            settlement_location::{SettlementLocationId, SettlementType},
+        },
+        resource::base_resource::Resource,
+    },
+    player::PlayerId,
+};
+
+use super::states::development_card::DevelopmentCard;
+
+/// Action a player is allowed to perform in the current state together with its valid targets.
+/// Serialized like [super::action_data::GameActionKind], e.g. `{"id": "BuildRoad", "data": {"road_ids": [...]}}`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "id", content = "data")]
+pub enum LegalAction {
+    BuyDevelopmentCard,
+    PlayDevelopmentCard {
+        cards: Vec<DevelopmentCard>,
+    },
+    BuildSettlement {
+        settlement_type: SettlementType,
+        settlement_ids: Vec<SettlementLocationId>,
+    },
+    BuildRoad {
+        road_ids: Vec<RoadLocationId>,
+    },
+    PlaceRobber {
+        targets: Vec<RobberTarget>,
+    },
+    RemoveCards {
+        count: usize,
+    },
+    RollDice,
+    EndTurn,
+
+    OfferTrade,
+    OfferBankTrade {
+        trade_options: Vec<BankTradeOption>,
+    },
+    AcceptTrade,
+    RejectTrade,
+    CompleteTrade {
+        accepted_player_ids: Vec<PlayerId>,
+    },
+    CancelTrade,
+}
+
+/// Tile the robber can be moved to and the players who can be robbed there
+#[derive(Debug, Clone, Serialize)]
+pub struct RobberTarget {
+    pub tile_location: CubeCoordinates,
+    pub robbable_player_ids: Vec<PlayerId>,
+}
+
+/// Best exchange rate of the player for giving `resource` to the bank
+#[derive(Debug, Clone, Serialize)]
+pub struct BankTradeOption {
+    pub resource: Resource,
+    pub offer_count: usize,
+    pub receive_count: usize,
+}
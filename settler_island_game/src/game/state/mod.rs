@@ -1,4 +1,6 @@
 pub mod action_data;
+pub mod game_rules;
+pub mod legal_action;
 pub mod state_action;
 pub mod state_machine;
 pub mod states;
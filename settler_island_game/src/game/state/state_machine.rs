@@ -1,9 +1,13 @@
-use std::{borrow::BorrowMut, cell::RefCell, collections::HashMap};
+use std::{
+    borrow::BorrowMut,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+};
 
 use log::error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use settler_island_util::state_id::HasStateId;
+use settler_island_util::state_id::{HasStateId, StateId};
 
 use crate::game::{
     board::{self, GameBoard},
@@ -11,32 +15,48 @@ use crate::game::{
     Game, GameError,
 };
 
-use super::states::game_error::GameErrorState;
+use super::{
+    action_data::GameActionKind, legal_action::LegalAction, states::game_error::GameErrorState,
+};
 
 pub type GameStateT = Box<dyn GameState + Send + Sync>;
 pub type GameActionResult = Result<(), GameError>;
 type GameStates = HashMap<String, GameStateT>;
 
-#[derive(Debug, Deserialize)]
-pub struct GameAction {
-    pub id: String,
-    pub data: Value,
-}
-
 pub trait GameState: HasStateId {
-    fn get_state(&self) -> Option<Value>;
+    /// State specific data which is sent to the clients
+    fn get_state(&self, game: &Game) -> Option<Value>;
     fn activate(&mut self, game: &Game);
     fn perform_action(
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult;
+    /// Actions the player can perform right now, empty if the player has to wait
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction>;
+    /// States this state can transition to, checked when the rules are built
+    fn get_transition_ids(&self) -> Vec<StateId>;
+    /// Internal fields which have to survive saving the game, `None` if the state has none
+    fn get_saved_state(&self) -> Option<Value> {
+        None
+    }
+    fn restore_saved_state(&mut self, saved_state: Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedStateMachine {
+    current_state_id: String,
+    states: BTreeMap<String, Value>,
 }
 
 pub struct StateMachine {
     current_state_id: String,
     states: GameStates,
+    taken_state_id: Option<String>,
+    activation_pending: bool,
 }
 
 impl StateMachine {
@@ -50,6 +70,8 @@ impl StateMachine {
         StateMachine {
             current_state_id: current_state_id,
             states: states_map,
+            taken_state_id: None,
+            activation_pending: false,
         }
     }
 
@@ -57,6 +79,52 @@ impl StateMachine {
         self.states.get(&self.current_state_id).unwrap()
     }
 
+    /// Data of the current state, `None` if the state is not registered
+    pub fn get_current_state_data(&self, game: &Game) -> Option<Value> {
+        match self.states.get(&self.current_state_id) {
+            None => {
+                error!("State {} not found", self.current_state_id);
+                None
+            }
+            Some(state) => state.get_state(game),
+        }
+    }
+
+    /// Legal actions of the current state, empty if the state is not registered
+    pub fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        match self.states.get(&self.current_state_id) {
+            None => {
+                error!("State {} not found", self.current_state_id);
+                vec![]
+            }
+            Some(state) => state.get_legal_actions(game, player_id),
+        }
+    }
+
+    pub fn get_current_state_id(&self) -> &String {
+        &self.current_state_id
+    }
+
+    /// Removes the current state from the machine so it can mutate the game while
+    /// the machine itself stays available for transitions.
+    /// The state has to be handed back with [StateMachine::restore_state]
+    pub fn take_current_state(&mut self) -> Option<GameStateT> {
+        let state = self.states.remove(&self.current_state_id)?;
+        self.taken_state_id = Some(self.current_state_id.clone());
+        Some(state)
+    }
+
+    pub fn restore_state(&mut self, game: &Game, mut state: GameStateT) {
+        let state_id = state.get_id().to_string();
+        // the taken state cannot be activated during its own action
+        if self.activation_pending && state_id == self.current_state_id {
+            state.activate(game);
+        }
+        self.activation_pending = false;
+        self.taken_state_id = None;
+        self.states.insert(state_id, state);
+    }
+
     pub fn transition_to(&mut self, game: &Game, state_id: &str) -> Result<(), String> {
         match self.states.get_mut(state_id) {
             Some(state) => {
@@ -64,6 +132,11 @@ impl StateMachine {
                 state.activate(game);
                 Ok(())
             }
+            None if self.taken_state_id.as_deref() == Some(state_id) => {
+                self.current_state_id = state_id.to_string();
+                self.activation_pending = true;
+                Ok(())
+            }
             None => {
                 let err = format!(
                     "State {} not found, transitioning to {}",
@@ -77,6 +150,46 @@ impl StateMachine {
         }
     }
 
+    pub fn get_saved_state_machine(&self) -> SavedStateMachine {
+        SavedStateMachine {
+            current_state_id: self.current_state_id.clone(),
+            states: self
+                .states
+                .iter()
+                .filter_map(|(state_id, state)| {
+                    state
+                        .get_saved_state()
+                        .map(|saved_state| (state_id.clone(), saved_state))
+                })
+                .collect(),
+        }
+    }
+
+    /// Restores the current state and the saved state fields without activating any state
+    pub fn restore_saved_state_machine(
+        &mut self,
+        saved_state_machine: SavedStateMachine,
+    ) -> Result<(), String> {
+        if !self
+            .states
+            .contains_key(&saved_state_machine.current_state_id)
+        {
+            return Err(format!(
+                "State {} not found",
+                saved_state_machine.current_state_id
+            ));
+        }
+
+        for (state_id, saved_state) in saved_state_machine.states {
+            match self.states.get_mut(&state_id) {
+                None => return Err(format!("State {} not found", state_id)),
+                Some(state) => state.restore_saved_state(saved_state)?,
+            }
+        }
+        self.current_state_id = saved_state_machine.current_state_id;
+        Ok(())
+    }
+
     pub fn to_error_state(&mut self) {
         self.current_state_id = GameErrorState::get_id().to_string();
     }
@@ -1,87 +1,153 @@
 use std::cell::{RefCell, RefMut};
 
 use log::trace;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::game::{
+    board::resource::{base_resource::Resource, player_resources::ResourceCollection},
+    event::GameEvent,
+    piece::PieceType,
     player::{Player, PlayerId},
     state::{
-        action_data::{DrawDevelopmentCardData, PlaceRobberData},
-        state_machine::{GameAction, GameActionResult},
+        action_data::{InventionData, MonopolyData, PlayDevelopmentCardData},
+        state_machine::GameActionResult,
     },
+    title::update_largest_army,
+    transaction::{ResourceAccount, ResourceTransaction},
     Game, GameError,
 };
 
-use self::build_n_free_roads::BuildNFreeRoads;
+use self::build_n_free_roads::{can_build_free_road, BuildNFreeRoads};
 
 use super::robber_relocate::RobberRelocate;
 
 pub mod build_n_free_roads;
 
-pub type DevelopmentCard = &'static str;
-
-pub const DEVELOPMENT_CARD_STREET_CONSTRUCTION: DevelopmentCard = "StreetConstruction";
-pub const DEVELOPMENT_CARD_MONOPOLY: DevelopmentCard = "Monopoly";
-pub const DEVELOPMENT_CARD_INVENTION: DevelopmentCard = "Invention";
-pub const DEVELOPMENT_CARD_KNIGHT: DevelopmentCard = "Knight";
-pub const DEVELOPMENT_CARD_VICTORY_POINT: DevelopmentCard = "VictoryPoint";
-
-#[derive(Debug, Deserialize, Clone)]
-pub struct InventionData {
-    pub resource_a: String,
-    pub resource_b: String,
+/// Development cards serialize to their names, e.g. `"Knight"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DevelopmentCard {
+    StreetConstruction,
+    Monopoly,
+    Invention,
+    Knight,
+    VictoryPoint,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct MonopolyData {
-    pub resource: String,
+impl std::fmt::Display for DevelopmentCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
-pub fn process_action(
+pub fn process_play_card(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    card_data: PlayDevelopmentCardData,
 ) -> GameActionResult {
-    let card_data = match serde_json::from_value::<DrawDevelopmentCardData>(action_data) {
-        Err(err) => {
-            trace!("Failed to parse draw development card data \"{}\"", err);
-            return Err(GameError::ActionDataInvalid);
+    match card_data {
+        PlayDevelopmentCardData::StreetConstruction => process_street_construction(game, player_id),
+        PlayDevelopmentCardData::Monopoly(monopoly_data) => {
+            process_monopoly(game, player_id, monopoly_data)
         }
-        Ok(data) => data,
-    };
+        PlayDevelopmentCardData::Invention(invention_data) => {
+            process_invention(game, player_id, invention_data)
+        }
+        PlayDevelopmentCardData::Knight => process_knight(game, player_id),
+        PlayDevelopmentCardData::VictoryPoint => {
+            trace!(
+                "Development card \"{}\" cannot be played",
+                card_data.get_card()
+            );
+            Err(GameError::DevelopmentCardNotPlayable)
+        }
+    }
+}
 
-    let card = card_data.card.as_str();
-    if let Err(err) = play_card(&mut game.get_player(*player_id).borrow_mut(), card) {
-        return Err(err);
+/// Knights are the only cards which can be played before the dice are rolled
+pub fn process_play_card_before_dice_roll(
+    game: &mut Game,
+    player_id: &PlayerId,
+    card_data: PlayDevelopmentCardData,
+) -> GameActionResult {
+    match card_data {
+        PlayDevelopmentCardData::Knight => process_knight(game, player_id),
+        card_data => {
+            trace!(
+                "Development card \"{}\" cannot be played before the dice roll",
+                card_data.get_card()
+            );
+            Err(GameError::DevelopmentCardNotPlayable)
+        }
     }
+}
 
-    match card {
-        DEVELOPMENT_CARD_STREET_CONSTRUCTION => Ok(()),
-        DEVELOPMENT_CARD_MONOPOLY => Ok(()),
-        DEVELOPMENT_CARD_INVENTION => Ok(()),
-        DEVELOPMENT_CARD_KNIGHT => Ok(()),
-        _ => Err(GameError::ActionNotAllowed),
+/// Cards the player can play in the current turn, victory points are never played
+pub fn get_playable_cards(game: &Game, player_id: &PlayerId) -> Vec<DevelopmentCard> {
+    let turn = game.get_turn();
+    let player = game.get_player(*player_id).borrow();
+    if player.has_played_development_card_in_turn(turn) {
+        return vec![];
     }
+
+    [
+        DevelopmentCard::StreetConstruction,
+        DevelopmentCard::Monopoly,
+        DevelopmentCard::Invention,
+        DevelopmentCard::Knight,
+    ]
+    .into_iter()
+    .filter(|card| player.get_playable_development_card_count(*card, turn) > 0)
+    .filter(|card| {
+        *card != DevelopmentCard::StreetConstruction || can_build_free_road(game, player_id)
+    })
+    .collect()
 }
 
-fn play_card(player: &mut RefMut<'_, Player>, card: &str) -> GameActionResult {
-    let cards = player.get_development_cards_mut();
-    let card_count = cards.get(card).unwrap_or(&0);
+/// Only a single card which was not bought in the current turn can be played per turn
+fn play_card(game: &mut Game, player_id: &PlayerId, card: DevelopmentCard) -> GameActionResult {
+    let turn = game.get_turn();
+    let mut player = game.get_player(*player_id).borrow_mut();
 
-    if *card_count == 0 {
-        return Err(GameError::NotEnoughResources);
+    if player.get_development_card_count(card) == 0 {
+        trace!("Player {} does not own a {} card", player_id, card);
+        return Err(GameError::DevelopmentCardNotOwned);
     }
 
-    cards.insert(card.to_string(), card_count - 1);
+    if player.has_played_development_card_in_turn(turn) {
+        trace!(
+            "Player {} already played a development card in turn {}",
+            player_id,
+            turn
+        );
+        return Err(GameError::DevelopmentCardAlreadyPlayedThisTurn);
+    }
+
+    if !player.play_development_card(card, turn) {
+        trace!(
+            "Player {} bought all {} cards in turn {}",
+            player_id,
+            card,
+            turn
+        );
+        return Err(GameError::DevelopmentCardBoughtThisTurn);
+    }
+    drop(player);
+
+    game.add_event(GameEvent::CardPlayed {
+        player_id: *player_id,
+        card,
+    });
     Ok(())
 }
 
-pub fn process_street_construction(
-    game: &mut Game,
-    player_id: &PlayerId,
-    action: GameAction,
-) -> GameActionResult {
+pub fn process_street_construction(game: &mut Game, player_id: &PlayerId) -> GameActionResult {
+    if !can_build_free_road(game, player_id) {
+        trace!("Player {} cannot build a free road", player_id);
+        return Err(GameError::DevelopmentCardNotPlayable);
+    }
+    play_card(game, player_id, DevelopmentCard::StreetConstruction)?;
+
     match game
         .get_state_machine()
         .borrow_mut()
@@ -95,69 +161,69 @@ pub fn process_street_construction(
 pub fn process_monopoly(
     game: &mut Game,
     player_id: &PlayerId,
-    action: GameAction,
+    monopoly_data: MonopolyData,
 ) -> GameActionResult {
-    let monopoly_data = match serde_json::from_value::<MonopolyData>(action.data) {
-        Err(err) => {
-            trace!("Failed to parse monopoly data \"{}\"", err);
-            return Err(GameError::ActionDataInvalid);
-        }
-        Ok(data) => data,
-    };
+    play_card(game, player_id, DevelopmentCard::Monopoly)?;
 
-    let mut resource_count: usize = 0;
     let resource = monopoly_data.resource;
+    let mut monopoly = ResourceTransaction::new();
     game.get_players()
         .iter()
-        .filter(|player| player.borrow().get_id() != player_id)
+        .map(|player| player.borrow())
+        .filter(|player| player.get_id() != player_id)
         .for_each(|player| {
-            let mut player = player.borrow_mut();
-            let player_resource_count =
-                *player.get_resources().get_resource(&resource).unwrap_or(&0);
-            player
-                .get_resources_mut()
-                .remove_resource(&resource, &player_resource_count);
-            resource_count += player_resource_count;
+            let player_resource_count = player.get_resources().get_resource(&resource);
+            monopoly.transfer_resource(
+                ResourceAccount::Player(*player.get_id()),
+                ResourceAccount::Player(*player_id),
+                &resource,
+                player_resource_count,
+            );
         });
 
-    game.get_player(*player_id)
-        .borrow_mut()
-        .get_resources_mut()
-        .add_resource(&resource, &resource_count);
+    monopoly.commit(game)?;
 
+    let resources = monopoly.get_paid_resources();
+    if !resources.is_empty() {
+        game.add_event(GameEvent::ResourcesMonopolized {
+            player_id: *player_id,
+            resources,
+        });
+    }
     Ok(())
 }
 
 pub fn process_invention(
     game: &mut Game,
     player_id: &PlayerId,
-    action: GameAction,
+    invention_data: InventionData,
 ) -> GameActionResult {
-    let invention_data = match serde_json::from_value::<InventionData>(action.data) {
-        Err(err) => {
-            trace!("Failed to parse invention data \"{}\"", err);
-            return Err(GameError::ActionDataInvalid);
-        }
-        Ok(data) => data,
-    };
+    let invented_resources = ResourceCollection::from([
+        (invention_data.resource_a, 1),
+        (invention_data.resource_b, 1),
+    ]);
+    let mut invention = ResourceTransaction::new();
+    invention.transfer(
+        ResourceAccount::Bank,
+        ResourceAccount::Player(*player_id),
+        invented_resources,
+    );
+    invention.validate(game)?;
 
-    game.get_player(*player_id)
-        .borrow_mut()
-        .get_resources_mut()
-        .add_resource(&invention_data.resource_a, &1);
-    game.get_player(*player_id)
-        .borrow_mut()
-        .get_resources_mut()
-        .add_resource(&invention_data.resource_b, &1);
+    play_card(game, player_id, DevelopmentCard::Invention)?;
+    invention.commit(game)?;
 
+    game.add_event(GameEvent::ResourcesInvented {
+        player_id: *player_id,
+        resources: invented_resources,
+    });
     Ok(())
 }
 
-pub fn process_knight(
-    game: &mut Game,
-    player_id: &PlayerId,
-    action: GameAction,
-) -> GameActionResult {
+pub fn process_knight(game: &mut Game, player_id: &PlayerId) -> GameActionResult {
+    play_card(game, player_id, DevelopmentCard::Knight)?;
+    update_largest_army(game);
+
     match game
         .get_state_machine()
         .borrow_mut()
@@ -167,3 +233,111 @@ pub fn process_knight(
         Ok(_) => Ok(()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::resource::{base_resource::Resource, player_resources::ResourceCollection},
+        event::GameEvent,
+        player::PlayerId,
+        state::{
+            action_data::{GameActionKind, InventionData, MonopolyData, PlayDevelopmentCardData},
+            states::select_action::SelectAction,
+        },
+        test_util::{generate_game, play_until},
+        Game,
+    };
+
+    use super::DevelopmentCard;
+
+    /// Plays until [SelectAction] and gives the current player a playable `card`
+    fn start_select_action(card: DevelopmentCard) -> (Game, PlayerId) {
+        let mut game = generate_game(3, 11);
+        assert!(play_until(&mut game, 200, |game| {
+            game.get_state_machine()
+                .borrow()
+                .get_current_state_id()
+                .as_str()
+                == SelectAction::get_id()
+        }));
+
+        let player_id = *game.get_current_player().borrow().get_id();
+        game.get_player(player_id)
+            .borrow_mut()
+            .add_development_card(card, game.get_turn() - 1);
+        (game, player_id)
+    }
+
+    #[test]
+    fn monopoly_emits_the_taken_resources() {
+        let (mut game, player_id) = start_select_action(DevelopmentCard::Monopoly);
+        let opponent_ids: Vec<PlayerId> = (0..game.get_player_count())
+            .filter(|opponent_id| *opponent_id != player_id)
+            .collect();
+        game.get_player(opponent_ids[0])
+            .borrow_mut()
+            .get_resources_mut()
+            .add_resource(&Resource::Wheat, &2);
+        let wheat_counts: Vec<usize> = opponent_ids
+            .iter()
+            .map(|opponent_id| {
+                game.get_player(*opponent_id)
+                    .borrow()
+                    .get_resources()
+                    .get_resource(&Resource::Wheat)
+            })
+            .collect();
+        let event_count = game.get_events().len();
+
+        game.perform_action(
+            &player_id,
+            GameActionKind::PlayDevelopmentCard(PlayDevelopmentCardData::Monopoly(MonopolyData {
+                resource: Resource::Wheat,
+            })),
+        )
+        .unwrap();
+
+        match &game.get_events()[event_count + 1..] {
+            [GameEvent::ResourcesMonopolized {
+                player_id: monopoly_player_id,
+                resources,
+            }] => {
+                assert_eq!(*monopoly_player_id, player_id);
+                for (opponent_id, wheat_count) in opponent_ids.iter().zip(wheat_counts) {
+                    let taken_resources = resources.get(opponent_id).copied().unwrap_or_default();
+                    assert_eq!(taken_resources[Resource::Wheat], wheat_count);
+                    assert_eq!(taken_resources.get_total(), wheat_count);
+                }
+            }
+            events => panic!("Unexpected events {:?}", events),
+        }
+    }
+
+    #[test]
+    fn invention_emits_the_invented_resources() {
+        let (mut game, player_id) = start_select_action(DevelopmentCard::Invention);
+        let event_count = game.get_events().len();
+
+        game.perform_action(
+            &player_id,
+            GameActionKind::PlayDevelopmentCard(PlayDevelopmentCardData::Invention(
+                InventionData {
+                    resource_a: Resource::Ore,
+                    resource_b: Resource::Ore,
+                },
+            )),
+        )
+        .unwrap();
+
+        match &game.get_events()[event_count + 1..] {
+            [GameEvent::ResourcesInvented {
+                player_id: inventing_player_id,
+                resources,
+            }] => {
+                assert_eq!(*inventing_player_id, player_id);
+                assert_eq!(*resources, ResourceCollection::from([(Resource::Ore, 2)]));
+            }
+            events => panic!("Unexpected events {:?}", events),
+        }
+    }
+}
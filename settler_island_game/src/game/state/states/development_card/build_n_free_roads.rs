@@ -1,17 +1,28 @@
 use log::{error, trace};
-use serde_json::Value;
+use serde_json::{json, Value};
 use settler_island_util_derive::HasStateId;
 
 use crate::game::{
+    event::GameEvent,
+    piece::PieceType,
     player::PlayerId,
     state::{
-        action_data::{PlaceRoadData, BUILD_ROAD_ACTION},
-        state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+        action_data::{GameActionKind, PlaceRoadData},
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, GameState, StateMachine},
         states::select_action::{self, SelectAction},
     },
+    title::update_longest_road,
     Game, GameError,
 };
 
+/// Free roads need a road piece and a location the player can build on
+pub fn can_build_free_road(game: &Game, player_id: &PlayerId) -> bool {
+    select_action::check_player_piece(game, player_id, &PieceType::Road).is_ok()
+        && !select_action::get_buildable_road_ids(game.get_board().get_settlement_map(), player_id)
+            .is_empty()
+}
+
 #[derive(HasStateId)]
 pub struct BuildNFreeRoads {
     free_roads: usize,
@@ -30,21 +41,14 @@ impl BuildNFreeRoads {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action_data: Value,
+        place_road_data: PlaceRoadData,
     ) -> GameActionResult {
         if self.free_roads_left == 0 {
             error!("Player {} has no free roads left", player_id);
             return Err(GameError::ActionFailed);
         }
 
-        let place_road_data = match serde_json::from_value::<PlaceRoadData>(action_data) {
-            Err(err) => {
-                trace!("Failed to parse place road data \"{}\"", err);
-                return Err(GameError::ActionDataInvalid);
-            }
-            Ok(data) => data,
-        };
-
+        select_action::check_player_piece(game, player_id, &PieceType::Road)?;
         if let Err(err) = select_action::place_road(
             game.get_board_mut().get_settlement_map_mut(),
             &place_road_data.road_id,
@@ -52,9 +56,19 @@ impl BuildNFreeRoads {
         ) {
             return Err(err);
         }
+        game.get_player(*player_id)
+            .borrow_mut()
+            .get_pieces_mut()
+            .take_piece(&PieceType::Road);
+        game.add_event(GameEvent::RoadBuilt {
+            player_id: *player_id,
+            road_id: place_road_data.road_id,
+        });
+        update_longest_road(game);
 
+        // remaining free roads are lost without road pieces or road locations
         self.free_roads_left -= 1;
-        if self.free_roads_left > 0 {
+        if self.free_roads_left > 0 && can_build_free_road(game, player_id) {
             return Ok(());
         }
 
@@ -74,23 +88,196 @@ impl GameState for BuildNFreeRoads {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
         if !game.is_player_turn(player_id) {
             return Err(GameError::NotPlayerTurn);
         }
 
-        match action.id.as_str() {
-            BUILD_ROAD_ACTION => self.process_build_road(game, player_id, action.data),
-            _ => Err(GameError::ActionNotAllowed),
+        match action {
+            GameActionKind::BuildRoad(place_road_data) => {
+                self.process_build_road(game, player_id, place_road_data)
+            }
+            GameActionKind::BuyDevelopmentCard
+            | GameActionKind::PlayDevelopmentCard(_)
+            | GameActionKind::BuildSettlement(_)
+            | GameActionKind::PlaceRobber(_)
+            | GameActionKind::RemoveCards(_)
+            | GameActionKind::RollDice
+            | GameActionKind::EndTurn
+            | GameActionKind::OfferTrade(_)
+            | GameActionKind::OfferBankTrade(_)
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(GameError::ActionNotAllowed),
+        }
+    }
+
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        if !game.is_player_turn(player_id)
+            || self.free_roads_left == 0
+            || !can_build_free_road(game, player_id)
+        {
+            return vec![];
         }
+
+        let road_ids =
+            select_action::get_buildable_road_ids(game.get_board().get_settlement_map(), player_id);
+        vec![LegalAction::BuildRoad { road_ids }]
+    }
+
+    fn get_state(&self, game: &Game) -> Option<serde_json::Value> {
+        Some(json!({
+            "free_roads": self.free_roads,
+            "free_roads_left": self.free_roads_left,
+        }))
     }
 
-    fn get_state(&self) -> Option<serde_json::Value> {
-        None
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![SelectAction::get_id()]
     }
 
     fn activate(&mut self, game: &Game) {
         self.free_roads_left = self.free_roads
     }
+
+    fn get_saved_state(&self) -> Option<Value> {
+        Some(json!({ "free_roads_left": self.free_roads_left }))
+    }
+
+    fn restore_saved_state(&mut self, saved_state: Value) -> Result<(), String> {
+        match saved_state["free_roads_left"].as_u64() {
+            None => Err("Saved free roads left are missing".to_string()),
+            Some(free_roads_left) => {
+                self.free_roads_left = free_roads_left as usize;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::location::road_location::{PlayerRoad, RoadLocationId},
+        player::PlayerId,
+        state::{
+            action_data::{GameActionKind, PlaceRoadData, PlayDevelopmentCardData},
+            legal_action::LegalAction,
+            states::{
+                development_card::{get_playable_cards, DevelopmentCard},
+                select_action::{self, SelectAction},
+            },
+        },
+        test_util::{generate_game, play_until},
+        Game, GameError,
+    };
+
+    /// Plays until the first player may act in [SelectAction] and gives them a playable street construction
+    fn start_select_action(seed: u64) -> (Game, PlayerId) {
+        let mut game = generate_game(3, seed);
+        assert!(play_until(&mut game, 200, |game| {
+            game.get_state_machine()
+                .borrow()
+                .get_current_state_id()
+                .as_str()
+                == SelectAction::get_id()
+        }));
+
+        let player_id = (0..game.get_player_count())
+            .find(|player_id| game.is_player_turn(player_id))
+            .unwrap();
+        game.get_player(player_id)
+            .borrow_mut()
+            .add_development_card(DevelopmentCard::StreetConstruction, game.get_turn() - 1);
+        (game, player_id)
+    }
+
+    /// Lets an opponent build on every road location the player could build on,
+    /// except `open_road_id` which gets only blocked roads around it
+    fn block_roads(game: &mut Game, player_id: &PlayerId, open_road_id: Option<&RoadLocationId>) {
+        let opponent_id = (player_id + 1) % game.get_player_count();
+        let settlement_map = game.get_board_mut().get_settlement_map_mut();
+        let mut road_ids = select_action::get_buildable_road_ids(settlement_map, player_id);
+        if let Some(open_road_id) = open_road_id {
+            let open_road = settlement_map.get_road(open_road_id).unwrap();
+            for settlement_id in [
+                open_road.get_settlement_a_id(),
+                open_road.get_settlement_b_id(),
+            ] {
+                road_ids.extend(
+                    settlement_map
+                        .get_settlement_roads(settlement_id)
+                        .into_iter()
+                        .filter(|road| !road.has_road())
+                        .map(|road| road.get_id().clone()),
+                );
+            }
+        }
+        road_ids.retain(|road_id| Some(road_id) != open_road_id);
+        road_ids.sort();
+        road_ids.dedup();
+
+        for road_id in road_ids {
+            settlement_map
+                .get_road_mut(&road_id)
+                .unwrap()
+                .build_road(PlayerRoad::from(opponent_id))
+                .unwrap();
+        }
+    }
+
+    fn get_current_state_id(game: &Game) -> String {
+        game.get_state_machine()
+            .borrow()
+            .get_current_state_id()
+            .clone()
+    }
+
+    #[test]
+    fn street_construction_is_not_playable_without_road_locations() {
+        let (mut game, player_id) = start_select_action(41);
+        block_roads(&mut game, &player_id, None);
+
+        assert!(
+            !get_playable_cards(&game, &player_id).contains(&DevelopmentCard::StreetConstruction)
+        );
+        assert_eq!(
+            game.perform_action(
+                &player_id,
+                GameActionKind::PlayDevelopmentCard(PlayDevelopmentCardData::StreetConstruction)
+            ),
+            Err(GameError::DevelopmentCardNotPlayable)
+        );
+        assert_eq!(get_current_state_id(&game), SelectAction::get_id());
+    }
+
+    #[test]
+    fn free_roads_end_when_no_road_location_is_left() {
+        let (mut game, player_id) = start_select_action(41);
+        let road_id = select_action::get_buildable_road_ids(
+            game.get_board().get_settlement_map(),
+            &player_id,
+        )[0]
+        .clone();
+        block_roads(&mut game, &player_id, Some(&road_id));
+
+        game.perform_action(
+            &player_id,
+            GameActionKind::PlayDevelopmentCard(PlayDevelopmentCardData::StreetConstruction),
+        )
+        .unwrap();
+        game.perform_action(
+            &player_id,
+            GameActionKind::BuildRoad(PlaceRoadData { road_id }),
+        )
+        .unwrap();
+
+        assert_eq!(get_current_state_id(&game), SelectAction::get_id());
+        assert!(game
+            .get_legal_actions(&player_id)
+            .iter()
+            .any(|legal_action| matches!(legal_action, LegalAction::EndTurn)));
+    }
 }
@@ -2,7 +2,11 @@ use settler_island_util_derive::HasStateId;
 
 use crate::game::{
     player::PlayerId,
-    state::state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+    state::{
+        action_data::GameActionKind,
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, GameState, StateMachine},
+    },
     Game, GameError,
 };
 
@@ -20,14 +24,22 @@ impl GameState for GameErrorState {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
         Err(GameError::ActionNotAllowed)
     }
 
-    fn get_state(&self) -> Option<serde_json::Value> {
+    fn get_legal_actions(&self, _game: &Game, _player_id: &PlayerId) -> Vec<LegalAction> {
+        vec![]
+    }
+
+    fn get_state(&self, game: &Game) -> Option<serde_json::Value> {
         None
     }
 
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![]
+    }
+
     fn activate(&mut self, game: &Game) {}
 }
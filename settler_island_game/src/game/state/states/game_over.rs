@@ -0,0 +1,98 @@
+use serde_json::{json, Value};
+use settler_island_util_derive::HasStateId;
+
+use crate::game::{
+    event::GameEvent,
+    player::PlayerId,
+    score::{get_standings, PlayerScore},
+    state::{
+        action_data::GameActionKind,
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, GameState},
+    },
+    Game, GameError,
+};
+
+/// Terminal state which is entered as soon as the current player reaches the
+/// victory points configured in the game settings
+#[derive(HasStateId)]
+pub struct GameOver {
+    winner: Option<PlayerId>,
+    standings: Vec<PlayerScore>,
+}
+
+impl GameOver {
+    pub fn new() -> Self {
+        GameOver {
+            winner: None,
+            standings: vec![],
+        }
+    }
+
+    pub fn get_winner(&self) -> &Option<PlayerId> {
+        &self.winner
+    }
+
+    pub fn get_standings(&self) -> &Vec<PlayerScore> {
+        &self.standings
+    }
+}
+
+impl Default for GameOver {
+    fn default() -> Self {
+        GameOver::new()
+    }
+}
+
+impl GameState for GameOver {
+    fn perform_action(
+        &mut self,
+        _game: &mut Game,
+        _player_id: &PlayerId,
+        _action: GameActionKind,
+    ) -> GameActionResult {
+        Err(GameError::ActionNotAllowed)
+    }
+
+    fn get_legal_actions(&self, _game: &Game, _player_id: &PlayerId) -> Vec<LegalAction> {
+        vec![]
+    }
+
+    fn get_state(&self, game: &Game) -> Option<serde_json::Value> {
+        Some(json!({
+            "winner": self.winner,
+            "standings": self.standings,
+        }))
+    }
+
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![]
+    }
+
+    fn activate(&mut self, game: &Game) {
+        self.winner = game
+            .get_events()
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                GameEvent::GameWon { player_id, .. } => Some(*player_id),
+                _ => None,
+            });
+        self.standings = get_standings(game);
+    }
+
+    fn get_saved_state(&self) -> Option<Value> {
+        Some(json!({
+            "winner": self.winner,
+            "standings": self.standings,
+        }))
+    }
+
+    fn restore_saved_state(&mut self, saved_state: Value) -> Result<(), String> {
+        self.winner = serde_json::from_value(saved_state["winner"].clone())
+            .map_err(|err| format!("Saved winner is invalid: {}", err))?;
+        self.standings = serde_json::from_value(saved_state["standings"].clone())
+            .map_err(|err| format!("Saved standings are invalid: {}", err))?;
+        Ok(())
+    }
+}
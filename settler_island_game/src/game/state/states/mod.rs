@@ -1,5 +1,6 @@
 pub mod development_card;
 pub mod game_error;
+pub mod game_over;
 pub mod robber_relocate;
 pub mod robber_remove_cards;
 pub mod roll_dice;
@@ -7,3 +8,31 @@ pub mod select_action;
 pub mod start_road_placement;
 pub mod start_village_placement;
 pub mod trading;
+
+use self::{
+    development_card::build_n_free_roads::BuildNFreeRoads, game_error::GameErrorState,
+    game_over::GameOver, robber_relocate::RobberRelocate, robber_remove_cards::RobberRemoveCards,
+    roll_dice::RollDice, select_action::SelectAction, start_road_placement::StartRoadPlacement,
+    start_village_placement::StartVillagePlacement, trading::Trading,
+};
+
+use super::state_machine::GameStateT;
+
+/// Free roads granted by the street construction card
+pub const STREET_CONSTRUCTION_ROADS: usize = 2;
+
+/// Every state of the base game, the game starts in the first state
+pub fn get_base_game_states() -> Vec<GameStateT> {
+    vec![
+        Box::new(StartVillagePlacement::new()),
+        Box::new(StartRoadPlacement::new()),
+        Box::new(RollDice::new()),
+        Box::new(SelectAction::new()),
+        Box::new(RobberRemoveCards::new()),
+        Box::new(RobberRelocate::new()),
+        Box::new(Trading::new()),
+        Box::new(BuildNFreeRoads::new(STREET_CONSTRUCTION_ROADS)),
+        Box::new(GameOver::new()),
+        Box::new(GameErrorState::new()),
+    ]
+}
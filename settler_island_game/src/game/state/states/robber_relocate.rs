@@ -1,18 +1,29 @@
+use std::iter::repeat_n;
+
 use log::trace;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use rand::seq::SliceRandom;
 use serde_json::Value;
 use settler_island_util_derive::HasStateId;
 
 use crate::game::{
-    board::resource::base_resource::ResourcedId,
+    board::{
+        hexagon::hexagon_tile::HexagonTile,
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
+    },
+    event::GameEvent,
     player::{Player, PlayerId},
+    random::GameRng,
     state::{
-        action_data::{PlaceRobberData, PLACE_ROBBER_ACTION},
-        state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+        action_data::{GameActionKind, PlaceRobberData},
+        legal_action::{LegalAction, RobberTarget},
+        state_machine::{GameActionResult, GameState, StateMachine},
     },
+    transaction::{ResourceAccount, ResourceTransaction},
     Game,
 };
 
+use super::{roll_dice::RollDice, select_action::SelectAction};
+
 #[derive(HasStateId)]
 pub struct RobberRelocate {}
 
@@ -25,16 +36,8 @@ impl RobberRelocate {
         &self,
         game: &mut Game,
         player_id: &PlayerId,
-        action_data: Value,
+        new_robber_location: PlaceRobberData,
     ) -> GameActionResult {
-        let new_robber_location = match serde_json::from_value::<PlaceRobberData>(action_data) {
-            Err(err) => {
-                trace!("Failed to parse robber location \"{}\"", err);
-                return Err(crate::game::GameError::ActionDataInvalid);
-            }
-            Ok(location) => location,
-        };
-
         let tile_map = game.get_board().get_tile_map();
         let tile = match tile_map.get_tile(&new_robber_location.tile_location) {
             None => {
@@ -44,70 +47,125 @@ impl RobberRelocate {
             Some(tile) => tile,
         };
 
-        let settlement_map = game.get_board().get_settlement_map();
-        let mut tile_players: Vec<PlayerId> = tile
-            .borrow()
-            .get_corner_settlement_ids()
-            .iter()
-            .filter_map(|tile_id| settlement_map.get_settlement(tile_id))
-            .filter_map(|settlement| settlement.get_settlement().clone())
-            .map(|settlement| *settlement.get_player_id())
-            .collect();
-        tile_players.dedup();
-
-        if !tile_players.contains(&new_robber_location.robbed_player_id) {
+        if tile.borrow().get_coordinates() == game.get_board().get_robber().get_assigned_tile() {
             trace!(
-                "Player {} not found at tile {:?}",
-                new_robber_location.robbed_player_id,
+                "Robber is already placed at {:?}",
                 new_robber_location.tile_location
             );
-            return Err(crate::game::GameError::ActionFailed);
+            return Err(crate::game::GameError::InvalidLocation);
         }
 
-        // exchange single resource from robbed player to player
-        let robbed_player = game.get_player(new_robber_location.robbed_player_id);
-        match RobberRelocate::get_random_resource_from_player(&robbed_player.borrow()) {
-            None => {
+        let robbable_players =
+            RobberRelocate::get_robbable_players(game, &tile.borrow(), player_id);
+
+        match new_robber_location.robbed_player_id {
+            None if !robbable_players.is_empty() => {
                 trace!(
-                    "Tried to rob player {} without resources",
-                    new_robber_location.robbed_player_id
+                    "Player {} has to rob one of {:?}",
+                    player_id,
+                    robbable_players
                 );
-                return Err(crate::game::GameError::ActionFailed);
+                return Err(crate::game::GameError::ActionDataInvalid);
             }
-            Some(resource) => {
-                robbed_player
-                    .borrow_mut()
-                    .get_resources_mut()
-                    .remove_resource(&resource, &1);
-                game.get_player(*player_id)
-                    .borrow_mut()
-                    .get_resources_mut()
-                    .add_resource(&resource, &1);
+            Some(robbed_player_id) if !robbable_players.contains(&robbed_player_id) => {
+                trace!(
+                    "Player {} cannot be robbed at tile {:?}",
+                    robbed_player_id,
+                    new_robber_location.tile_location
+                );
+                return Err(crate::game::GameError::ActionFailed);
             }
+            _ => (),
         }
 
-        Ok(())
-    }
+        game.get_board_mut()
+            .move_robber(new_robber_location.tile_location);
+        game.add_event(GameEvent::RobberMoved {
+            player_id: *player_id,
+            tile_location: new_robber_location.tile_location,
+        });
 
-    fn get_random_resource_from_player(player: &Player) -> Option<ResourcedId> {
-        if player.get_resources().get_total_resources() == 0 {
-            return None;
+        if let Some(robbed_player_id) = new_robber_location.robbed_player_id {
+            // exchange single resource from robbed player to player
+            let robbed_resources = game
+                .get_player(robbed_player_id)
+                .borrow()
+                .get_resources()
+                .get_resources();
+            let resource = match RobberRelocate::get_random_resource(
+                robbed_resources,
+                game.get_board_mut().get_robbery_rng_mut(),
+            ) {
+                None => {
+                    trace!("Tried to rob player {} without resources", robbed_player_id);
+                    return Err(crate::game::GameError::ActionFailed);
+                }
+                Some(resource) => resource,
+            };
+            ResourceTransaction::new()
+                .transfer_resource(
+                    ResourceAccount::Player(robbed_player_id),
+                    ResourceAccount::Player(*player_id),
+                    &resource,
+                    1,
+                )
+                .commit(game)?;
+            game.add_event(GameEvent::ResourceStolen {
+                player_id: *player_id,
+                robbed_player_id,
+                resource: Some(resource),
+            });
         }
 
-        let resources = player.get_resources().get_resources();
-        let player_resource_ids: Vec<&ResourcedId> = resources.keys().collect();
-        loop {
-            let resource = *player_resource_ids.choose(&mut thread_rng()).unwrap();
-            match resources.get(resource) {
-                Some(count) => {
-                    if *count > 0 {
-                        return Some(resource.clone());
-                    }
-                }
-                _ => (),
-            }
+        // knights can be played before the dice roll
+        let next_state_id = match game.get_dice_roll() {
+            None => RollDice::get_id(),
+            Some(_) => SelectAction::get_id(),
+        };
+        match game
+            .get_state_machine()
+            .borrow_mut()
+            .transition_to(game, next_state_id)
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(crate::game::GameError::ActionFailed),
         }
-        None
+    }
+
+    /// Only other players with resources and a settlement next to the tile can be robbed
+    fn get_robbable_players(
+        game: &Game,
+        tile: &HexagonTile,
+        player_id: &PlayerId,
+    ) -> Vec<PlayerId> {
+        let settlement_map = game.get_board().get_settlement_map();
+        let mut robbable_players: Vec<PlayerId> = tile
+            .get_corner_settlement_ids()
+            .iter()
+            .filter_map(|tile_id| settlement_map.get_settlement(tile_id))
+            .filter_map(|settlement| settlement.get_settlement().clone())
+            .map(|settlement| *settlement.get_player_id())
+            .filter(|tile_player_id| tile_player_id != player_id)
+            .filter(|tile_player_id| {
+                game.get_player(*tile_player_id)
+                    .borrow()
+                    .get_resources()
+                    .get_total_resources()
+                    > 0
+            })
+            .collect();
+        robbable_players.sort();
+        robbable_players.dedup();
+        robbable_players
+    }
+
+    /// Every resource card of the player has the same chance to be robbed
+    fn get_random_resource(resources: ResourceCollection, rng: &mut GameRng) -> Option<Resource> {
+        let player_resource_cards: Vec<Resource> = resources
+            .iter()
+            .flat_map(|(resource, count)| repeat_n(resource, count))
+            .collect();
+        player_resource_cards.choose(rng).copied()
     }
 }
 
@@ -116,17 +174,61 @@ impl GameState for RobberRelocate {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
-        match action.id.as_str() {
-            PLACE_ROBBER_ACTION => self.process_place_robber(game, player_id, action.data),
-            _ => Err(crate::game::GameError::ActionNotAllowed),
+        if !game.is_player_turn(player_id) {
+            return Err(crate::game::GameError::NotPlayerTurn);
+        }
+
+        match action {
+            GameActionKind::PlaceRobber(place_robber_data) => {
+                self.process_place_robber(game, player_id, place_robber_data)
+            }
+            GameActionKind::BuyDevelopmentCard
+            | GameActionKind::PlayDevelopmentCard(_)
+            | GameActionKind::BuildSettlement(_)
+            | GameActionKind::BuildRoad(_)
+            | GameActionKind::RemoveCards(_)
+            | GameActionKind::RollDice
+            | GameActionKind::EndTurn
+            | GameActionKind::OfferTrade(_)
+            | GameActionKind::OfferBankTrade(_)
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(crate::game::GameError::ActionNotAllowed),
         }
     }
 
-    fn get_state(&self) -> Option<Value> {
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        if !game.is_player_turn(player_id) {
+            return vec![];
+        }
+
+        let robber_tile = game.get_board().get_robber().get_assigned_tile();
+        let mut targets: Vec<RobberTarget> = game
+            .get_board()
+            .get_tile_map()
+            .get_tiles()
+            .into_iter()
+            .map(|tile| tile.borrow())
+            .filter(|tile| tile.get_coordinates() != robber_tile)
+            .map(|tile| RobberTarget {
+                tile_location: *tile.get_coordinates(),
+                robbable_player_ids: RobberRelocate::get_robbable_players(game, &tile, player_id),
+            })
+            .collect();
+        targets.sort_by_key(|target| (target.tile_location.q, target.tile_location.r));
+        vec![LegalAction::PlaceRobber { targets }]
+    }
+
+    fn get_state(&self, game: &Game) -> Option<Value> {
         None
     }
 
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![RollDice::get_id(), SelectAction::get_id()]
+    }
+
     fn activate(&mut self, game: &Game) {}
 }
@@ -1,16 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use log::trace;
-use serde_json::Value;
+use serde_json::{json, Value};
 use settler_island_util_derive::HasStateId;
 
 use crate::game::{
     board::resource::player_resources::{get_total_resources, ResourceCollection},
+    event::GameEvent,
     player::{Player, PlayerId},
     state::{
-        action_data::REMOVE_CARDS_ACTION,
-        state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+        action_data::GameActionKind,
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, GameState, StateMachine},
     },
+    transaction::{ResourceAccount, ResourceTransaction},
     Game,
 };
 
@@ -40,16 +43,56 @@ impl GameState for RobberRemoveCards {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
-        match action.id.as_str() {
-            REMOVE_CARDS_ACTION => process_remove_cards(game, player_id, action.data),
-            _ => Err(crate::game::GameError::ActionNotAllowed),
+        match action {
+            GameActionKind::RemoveCards(removed_cards) => {
+                process_remove_cards(game, player_id, removed_cards)
+            }
+            GameActionKind::BuyDevelopmentCard
+            | GameActionKind::PlayDevelopmentCard(_)
+            | GameActionKind::BuildSettlement(_)
+            | GameActionKind::BuildRoad(_)
+            | GameActionKind::PlaceRobber(_)
+            | GameActionKind::RollDice
+            | GameActionKind::EndTurn
+            | GameActionKind::OfferTrade(_)
+            | GameActionKind::OfferBankTrade(_)
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(crate::game::GameError::ActionNotAllowed),
+        }
+    }
+
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        let player = game.get_player(*player_id).borrow();
+        if !player_needs_to_remove_resources(&player) {
+            return vec![];
         }
+        vec![LegalAction::RemoveCards {
+            count: player.get_resources().get_total_resources() - 7,
+        }]
+    }
+
+    fn get_state(&self, game: &Game) -> Option<Value> {
+        let pending_discards: BTreeMap<PlayerId, usize> = game
+            .get_players()
+            .iter()
+            .map(|player| player.borrow())
+            .filter(|player| player_needs_to_remove_resources(player))
+            .map(|player| {
+                (
+                    *player.get_id(),
+                    player.get_resources().get_total_resources() - 7,
+                )
+            })
+            .collect();
+        Some(json!({ "pending_discards": pending_discards }))
     }
 
-    fn get_state(&self) -> Option<Value> {
-        None
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![RobberRelocate::get_id()]
     }
 
     fn activate(&mut self, game: &Game) {
@@ -60,11 +103,9 @@ impl GameState for RobberRemoveCards {
 fn process_remove_cards(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    removed_cards: ResourceCollection,
 ) -> GameActionResult {
-    if let Err(err) = remove_player_cards(game, player_id, action_data) {
-        return Err(err);
-    }
+    remove_player_cards(game, player_id, removed_cards)?;
 
     match has_a_player_too_many_cards(game) {
         true => Ok(()),
@@ -95,29 +136,76 @@ fn is_player_removing_invalid_resources_count(
 fn remove_player_cards(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    removed_cards: ResourceCollection,
 ) -> GameActionResult {
-    let player = game.get_player(*player_id).borrow();
-    if !player_needs_to_remove_resources(&player) {
-        trace!("Player does not need to remove resources");
-        return Err(crate::game::GameError::ActionNotAllowed);
-    }
-    let removed_cards = match serde_json::from_value::<ResourceCollection>(action_data) {
-        Err(err) => {
-            trace!("Failed to parse removed cards \"{}\"", err);
-            return Err(crate::game::GameError::ActionDataInvalid);
+    {
+        let player = game.get_player(*player_id).borrow();
+        if !player_needs_to_remove_resources(&player) {
+            trace!("Player does not need to remove resources");
+            return Err(crate::game::GameError::ActionNotAllowed);
+        }
+        if !player.get_resources().has_resources(&removed_cards) {
+            trace!("Player does not own the removed resources");
+            return Err(crate::game::GameError::NotEnoughResources);
         }
-        Ok(cards) => cards,
-    };
 
-    if is_player_removing_invalid_resources_count(&player, &removed_cards) {
-        trace!("Player did not remove the necessary resource amount");
-        return Err(crate::game::GameError::ActionFailed);
+        if is_player_removing_invalid_resources_count(&player, &removed_cards) {
+            trace!("Player did not remove the necessary resource amount");
+            return Err(crate::game::GameError::ActionFailed);
+        }
     }
 
-    game.get_player(*player_id)
-        .borrow_mut()
-        .get_resources_mut()
-        .remove_resources(&removed_cards);
+    ResourceTransaction::new()
+        .transfer(
+            ResourceAccount::Player(*player_id),
+            ResourceAccount::Bank,
+            removed_cards,
+        )
+        .commit(game)?;
+
+    game.add_event(GameEvent::ResourcesRemoved {
+        player_id: *player_id,
+        resources: removed_cards,
+    });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::resource::{base_resource::Resource, player_resources::ResourceCollection},
+        event::GameEvent,
+        state::action_data::GameActionKind,
+        test_util::generate_game,
+    };
+
+    use super::RobberRemoveCards;
+
+    #[test]
+    fn removed_cards_are_emitted() {
+        let mut game = generate_game(3, 5);
+        game.get_player(1)
+            .borrow_mut()
+            .get_resources_mut()
+            .add_resource(&Resource::Sheep, &9);
+        game.get_state_machine()
+            .borrow_mut()
+            .transition_to(&game, RobberRemoveCards::get_id())
+            .unwrap();
+        let removed_cards = ResourceCollection::from([(Resource::Sheep, 2)]);
+
+        game.perform_action(&1, GameActionKind::RemoveCards(removed_cards))
+            .unwrap();
+
+        match game.get_events().as_slice() {
+            [GameEvent::ResourcesRemoved {
+                player_id,
+                resources,
+            }] => {
+                assert_eq!(*player_id, 1);
+                assert_eq!(*resources, removed_cards);
+            }
+            events => panic!("Unexpected events {:?}", events),
+        }
+    }
+}
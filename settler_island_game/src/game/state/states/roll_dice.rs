@@ -1,26 +1,31 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, collections::HashMap};
 
-use log::error;
+use log::{debug, error};
 use settler_island_util_derive::HasStateId;
 
 use crate::game::{
     board::{
         hexagon::hexagon_tile::{HexagonTile, TileType},
         location::settlement_location::{PlayerSettlement, SettlementType},
-        resource::base_resource::ResourcedId,
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
         DoubleDiceRoll,
     },
+    event::GameEvent,
     player::{Player, PlayerId},
     state::{
-        action_data::ROLL_DICE_ACTION,
-        state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+        action_data::GameActionKind,
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, GameState, StateMachine},
     },
+    transaction::{ResourceAccount, ResourceTransaction},
     Game, GameError,
 };
 
 use super::{
+    development_card::{self, DevelopmentCard},
     robber_relocate::RobberRelocate,
     robber_remove_cards::{has_a_player_too_many_cards, RobberRemoveCards},
+    select_action::SelectAction,
 };
 
 #[derive(HasStateId)]
@@ -33,9 +38,24 @@ impl RollDice {
 
     fn process_roll_dice(&self, game: &mut Game, player_id: &PlayerId) -> GameActionResult {
         let dice_roll = game.get_board_mut().roll_dice();
+        game.set_dice_roll(dice_roll);
+        game.add_event(GameEvent::DiceRolled {
+            player_id: *player_id,
+            dice_roll,
+        });
         match dice_roll.get_total() {
             7 => self.transition_to_robber_state(game),
-            value => self.add_resources(game, &value),
+            value => {
+                self.add_resources(game, &value)?;
+                match game
+                    .get_state_machine()
+                    .borrow_mut()
+                    .transition_to(game, SelectAction::get_id())
+                {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(GameError::ActionFailed),
+                }
+            }
         }
     }
 
@@ -60,8 +80,29 @@ impl RollDice {
     }
 
     fn add_resources(&self, game: &mut Game, dice_roll_value: &u8) -> GameActionResult {
+        let resource_demands = self.get_resource_demands(game, dice_roll_value)?;
+        let mut production = ResourceTransaction::new();
+        for (resource, player_demands) in resource_demands {
+            self.pay_resource(game, &mut production, &resource, player_demands);
+        }
+        production.commit(game)?;
+
+        let resources = production.get_received_resources();
+        if !resources.is_empty() {
+            game.add_event(GameEvent::ResourcesProduced { resources });
+        }
+        Ok(())
+    }
+
+    /// Collects the resources every player receives from the tiles with the rolled value
+    fn get_resource_demands(
+        &self,
+        game: &Game,
+        dice_roll_value: &u8,
+    ) -> Result<HashMap<Resource, HashMap<PlayerId, usize>>, GameError> {
+        let mut resource_demands = HashMap::<Resource, HashMap<PlayerId, usize>>::new();
         let board = game.get_board();
-        let tiles = board.get_tiles_by_dice_value(&dice_roll_value);
+        let tiles = board.get_tiles_by_dice_value(dice_roll_value);
         for tile in tiles {
             let tile_borrow = tile.borrow();
 
@@ -91,29 +132,53 @@ impl RollDice {
                     Some(player_settlement) => player_settlement,
                 };
 
-                self.add_settlement_resource(game, &player_settlement, resource);
+                *resource_demands
+                    .entry(*resource)
+                    .or_default()
+                    .entry(*player_settlement.get_player_id())
+                    .or_insert(0) += self.get_settlement_resource_count(player_settlement);
             }
         }
 
-        Ok(())
+        Ok(resource_demands)
     }
 
-    fn add_settlement_resource(
-        &self,
-        game: &Game,
-        settlement: &PlayerSettlement,
-        resource: &ResourcedId,
-    ) -> Result<(), GameError> {
-        let resource_factor: usize = match settlement.get_settlement_type() {
+    fn get_settlement_resource_count(&self, settlement: &PlayerSettlement) -> usize {
+        match settlement.get_settlement_type() {
             SettlementType::Village => 1,
             SettlementType::City => 2,
-        };
+        }
+    }
 
-        game.get_player(*settlement.get_player_id())
-            .borrow_mut()
-            .get_resources_mut()
-            .add_resource(resource, &resource_factor);
-        Ok(())
+    /// Nobody receives the resource if the bank cannot pay every player.
+    /// A single player receives the remaining resources of the bank instead
+    fn pay_resource(
+        &self,
+        game: &Game,
+        production: &mut ResourceTransaction,
+        resource: &Resource,
+        player_demands: HashMap<PlayerId, usize>,
+    ) {
+        let bank_resource_count = game.get_board().get_bank().get_resource_count(resource);
+        let total_demand: usize = player_demands.values().sum();
+        if total_demand > bank_resource_count && player_demands.len() > 1 {
+            debug!(
+                "Bank cannot pay {} {} to {} players",
+                total_demand,
+                resource,
+                player_demands.len()
+            );
+            return;
+        }
+
+        for (player_id, demand) in player_demands {
+            production.transfer_resource(
+                ResourceAccount::Bank,
+                ResourceAccount::Player(player_id),
+                resource,
+                demand.min(bank_resource_count),
+            );
+        }
     }
 }
 
@@ -122,21 +187,58 @@ impl GameState for RollDice {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
         if !game.is_player_turn(player_id) {
             return Err(GameError::NotPlayerTurn);
         }
 
-        match action.id.as_str() {
-            ROLL_DICE_ACTION => self.process_roll_dice(game, player_id),
-            _ => Err(crate::game::GameError::ActionNotAllowed),
+        match action {
+            GameActionKind::RollDice => self.process_roll_dice(game, player_id),
+            GameActionKind::PlayDevelopmentCard(card_data) => {
+                development_card::process_play_card_before_dice_roll(game, player_id, card_data)
+            }
+            GameActionKind::BuyDevelopmentCard
+            | GameActionKind::BuildSettlement(_)
+            | GameActionKind::BuildRoad(_)
+            | GameActionKind::PlaceRobber(_)
+            | GameActionKind::RemoveCards(_)
+            | GameActionKind::EndTurn
+            | GameActionKind::OfferTrade(_)
+            | GameActionKind::OfferBankTrade(_)
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(crate::game::GameError::ActionNotAllowed),
         }
     }
 
-    fn get_state(&self) -> Option<serde_json::Value> {
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        if !game.is_player_turn(player_id) {
+            return vec![];
+        }
+
+        let mut legal_actions = vec![LegalAction::RollDice];
+        if development_card::get_playable_cards(game, player_id).contains(&DevelopmentCard::Knight)
+        {
+            legal_actions.push(LegalAction::PlayDevelopmentCard {
+                cards: vec![DevelopmentCard::Knight],
+            });
+        }
+        legal_actions
+    }
+
+    fn get_state(&self, game: &Game) -> Option<serde_json::Value> {
         None
     }
 
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![
+            SelectAction::get_id(),
+            RobberRemoveCards::get_id(),
+            RobberRelocate::get_id(),
+        ]
+    }
+
     fn activate(&mut self, game: &Game) {}
 }
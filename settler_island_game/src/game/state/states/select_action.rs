@@ -10,30 +10,31 @@ use settler_island_util_derive::HasStateId;
 use crate::game::{
     board::{
         location::{
-            road_location::PlayerRoad,
-            settlement_location::{SettlementLocation, SettlementType},
+            road_location::{PlayerRoad, RoadLocationId},
+            settlement_location::{SettlementLocation, SettlementLocationId, SettlementType},
             settlement_map::SettlementMap,
         },
-        resource::{
-            base_resource::{
-                RESOURCE_CLAY, RESOURCE_ORE, RESOURCE_SHEEP, RESOURCE_WHEAT, RESOURCE_WOOD,
-            },
-            player_resources::ResourceCollection,
-        },
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
     },
+    event::GameEvent,
+    piece::PieceType,
     player::{Player, PlayerId},
     state::{
-        action_data::{
-            PlaceRoadData, PlaceSettlementData, BUILD_ROAD_ACTION, BUILD_SETTLEMENT_ACTION,
-            DRAW_DEVELOPMENT_CARD_ACTION, END_TURN_ACTION, OFFER_BANK_TRADE_ACTION,
-            OFFER_TRADE_ACTION,
-        },
-        state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+        action_data::{GameActionKind, PlaceRoadData, PlaceSettlementData},
+        legal_action::LegalAction,
+        state_machine::{GameActionResult, GameState, StateMachine},
     },
+    title::update_longest_road,
+    transaction::{ResourceAccount, ResourceTransaction},
     Game, GameError,
 };
 
-use super::{development_card, roll_dice::RollDice, trading};
+use super::{
+    development_card::{self, build_n_free_roads::BuildNFreeRoads},
+    robber_relocate::RobberRelocate,
+    roll_dice::RollDice,
+    trading::{self, Trading},
+};
 
 #[derive(HasStateId)]
 pub struct SelectAction {}
@@ -49,184 +50,303 @@ impl GameState for SelectAction {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
         if !game.is_player_turn(player_id) {
             return Err(GameError::NotPlayerTurn);
         }
 
-        match action.id.as_str() {
-            BUILD_SETTLEMENT_ACTION => process_build_settlement(game, player_id, action.data),
-            BUILD_ROAD_ACTION => process_build_road(game, player_id, action.data),
-            DRAW_DEVELOPMENT_CARD_ACTION => {
-                development_card::process_action(game, player_id, action.data)
+        match action {
+            GameActionKind::BuildSettlement(place_settlement_data) => {
+                process_build_settlement(game, player_id, place_settlement_data)
+            }
+            GameActionKind::BuildRoad(place_road_data) => {
+                process_build_road(game, player_id, place_road_data)
+            }
+            GameActionKind::BuyDevelopmentCard => process_buy_development_card(game, player_id),
+            GameActionKind::PlayDevelopmentCard(card_data) => {
+                development_card::process_play_card(game, player_id, card_data)
             }
-            END_TURN_ACTION => process_end_turn(game, player_id, action.data),
-            OFFER_TRADE_ACTION => trading::process_trade_offer(game, player_id, action.data),
-            OFFER_BANK_TRADE_ACTION => {
-                trading::process_bank_trade_offer(game, player_id, action.data)
+            GameActionKind::EndTurn => process_end_turn(game, player_id),
+            GameActionKind::OfferTrade(trade_data) => {
+                trading::process_trade_offer(game, player_id, trade_data)
             }
-            _ => Err(crate::game::GameError::ActionNotAllowed),
+            GameActionKind::OfferBankTrade(trade_data) => {
+                trading::process_bank_trade_offer(game, player_id, trade_data)
+            }
+            GameActionKind::PlaceRobber(_)
+            | GameActionKind::RemoveCards(_)
+            | GameActionKind::RollDice
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(crate::game::GameError::ActionNotAllowed),
         }
     }
 
-    fn get_state(&self) -> Option<Value> {
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        if !game.is_player_turn(player_id) {
+            return vec![];
+        }
+
+        let mut legal_actions = vec![];
+        let settlement_map = game.get_board().get_settlement_map();
+        if can_buy_piece(game, player_id, &PieceType::Road, get_road_cost()) {
+            let road_ids = get_buildable_road_ids(settlement_map, player_id);
+            if !road_ids.is_empty() {
+                legal_actions.push(LegalAction::BuildRoad { road_ids });
+            }
+        }
+
+        for (settlement_type, resource_cost) in [
+            (SettlementType::Village, get_village_cost()),
+            (SettlementType::City, get_city_cost()),
+        ] {
+            let piece_type = PieceType::from_settlement_type(&settlement_type);
+            if !can_buy_piece(game, player_id, &piece_type, resource_cost) {
+                continue;
+            }
+            let settlement_ids =
+                get_buildable_settlement_ids(settlement_map, &settlement_type, player_id);
+            if !settlement_ids.is_empty() {
+                legal_actions.push(LegalAction::BuildSettlement {
+                    settlement_type,
+                    settlement_ids,
+                });
+            }
+        }
+
+        let player = game.get_player(*player_id).borrow();
+        if game.get_board().get_development_cards_left() > 0
+            && player
+                .get_resources()
+                .has_resources(&get_development_card_cost())
+        {
+            legal_actions.push(LegalAction::BuyDevelopmentCard);
+        }
+
+        let cards = development_card::get_playable_cards(game, player_id);
+        if !cards.is_empty() {
+            legal_actions.push(LegalAction::PlayDevelopmentCard { cards });
+        }
+
+        if player.get_resources().get_total_resources() > 0 {
+            legal_actions.push(LegalAction::OfferTrade);
+        }
+
+        let trade_options = trading::get_bank_trade_options(game, player_id);
+        if !trade_options.is_empty() {
+            legal_actions.push(LegalAction::OfferBankTrade { trade_options });
+        }
+
+        legal_actions.push(LegalAction::EndTurn);
+        legal_actions
+    }
+
+    fn get_state(&self, game: &Game) -> Option<Value> {
         None
     }
 
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![
+            RollDice::get_id(),
+            BuildNFreeRoads::get_id(),
+            RobberRelocate::get_id(),
+            Trading::get_id(),
+        ]
+    }
+
     fn activate(&mut self, game: &Game) {}
 }
 
 fn process_build_road(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    place_road_data: PlaceRoadData,
 ) -> GameActionResult {
-    let place_road_data = match serde_json::from_value::<PlaceRoadData>(action_data) {
-        Err(err) => {
-            trace!("Failed to parse place road data \"{}\"", err);
-            return Err(GameError::ActionDataInvalid);
-        }
-        Ok(data) => data,
-    };
-
-    let player = game.get_player(*player_id);
-    if let Err(err) = remove_resources(&mut player.borrow_mut(), &get_road_cost()) {
-        return Err(err);
-    }
+    check_player_piece(game, player_id, &PieceType::Road)?;
+    let payment = get_payment(player_id, get_road_cost());
+    payment.validate(game)?;
 
-    match place_road(
+    place_road(
         game.get_board_mut().get_settlement_map_mut(),
         &place_road_data.road_id,
         player_id,
-    ) {
-        Err(err) => Err(err),
-        Ok(_) => Ok(()),
-    }
+    )?;
+    payment.commit(game)?;
+    game.get_player(*player_id)
+        .borrow_mut()
+        .get_pieces_mut()
+        .take_piece(&PieceType::Road);
+    game.add_event(GameEvent::RoadBuilt {
+        player_id: *player_id,
+        road_id: place_road_data.road_id,
+    });
+
+    update_longest_road(game);
+    Ok(())
 }
 
 fn process_build_settlement(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    place_settlement_data: PlaceSettlementData,
 ) -> GameActionResult {
-    let place_settlement_data = match serde_json::from_value::<PlaceSettlementData>(action_data) {
-        Err(err) => {
-            trace!("Failed to parse place road data \"{}\"", err);
-            return Err(GameError::ActionDataInvalid);
-        }
-        Ok(data) => data,
-    };
-
     let resource_cost = match place_settlement_data.settlement_type {
         SettlementType::Village => get_village_cost(),
         SettlementType::City => get_city_cost(),
     };
 
-    if let Err(err) = remove_resources(
-        &mut game.get_player(*player_id).borrow_mut(),
-        &resource_cost,
-    ) {
-        return Err(err);
-    }
+    let piece_type = PieceType::from_settlement_type(&place_settlement_data.settlement_type);
+    check_player_piece(game, player_id, &piece_type)?;
+    let payment = get_payment(player_id, resource_cost);
+    payment.validate(game)?;
 
     place_settlement(
         game.get_board_mut().get_settlement_map_mut(),
         &place_settlement_data.settlement_id,
         place_settlement_data.settlement_type,
         player_id,
-    )
+    )?;
+    payment.commit(game)?;
+    {
+        let mut player = game.get_player(*player_id).borrow_mut();
+        player.get_pieces_mut().take_piece(&piece_type);
+        // the upgraded village goes back to the inventory
+        if piece_type == PieceType::City {
+            player.get_pieces_mut().return_piece(&PieceType::Village);
+        }
+    }
+    let player_id = *player_id;
+    let settlement_id = place_settlement_data.settlement_id;
+    game.add_event(match piece_type {
+        PieceType::City => GameEvent::CityUpgraded {
+            player_id,
+            settlement_id,
+        },
+        _ => GameEvent::SettlementBuilt {
+            player_id,
+            settlement_id,
+        },
+    });
+
+    // a village can interrupt the road of another player
+    update_longest_road(game);
+    Ok(())
 }
 
-fn process_draw_development_card(
-    game: &mut Game,
-    player_id: &PlayerId,
-    action_data: Value,
-) -> GameActionResult {
-    Err(GameError::ActionNotAllowed)
+fn process_buy_development_card(game: &mut Game, player_id: &PlayerId) -> GameActionResult {
+    if game.get_board().get_development_cards_left() == 0 {
+        trace!("No development cards left to buy");
+        return Err(GameError::NoDevelopmentCardsLeft);
+    }
+
+    get_payment(player_id, get_development_card_cost()).commit(game)?;
+
+    let card = match game.get_board_mut().draw_development_card() {
+        None => {
+            error!("Failed to draw a development card");
+            return Err(GameError::ActionFailed);
+        }
+        Some(card) => card,
+    };
+
+    game.get_player(*player_id)
+        .borrow_mut()
+        .add_development_card(card, game.get_turn());
+    game.add_event(GameEvent::CardBought {
+        player_id: *player_id,
+        card: Some(card),
+    });
+    Ok(())
 }
 
-fn process_end_turn(game: &mut Game, player_id: &PlayerId, action_data: Value) -> GameActionResult {
+fn process_end_turn(game: &mut Game, player_id: &PlayerId) -> GameActionResult {
     game.end_turn();
     match game
         .get_state_machine()
         .borrow_mut()
         .transition_to(game, RollDice::get_id())
     {
-        Err(err) => Err(GameError::ActionFailed),
+        Err(_) => Err(GameError::ActionFailed),
         Ok(_) => Ok(()),
     }
 }
 
-fn process_trade_offer(
-    game: &mut Game,
-    player_id: &PlayerId,
-    action_data: Value,
-) -> GameActionResult {
-    // TODO create trade offer
-    // TODO transition to trading state
-    Err(GameError::ActionNotAllowed)
-}
-
 fn get_road_cost() -> ResourceCollection {
-    ResourceCollection::from([
-        (RESOURCE_CLAY.to_string(), 1),
-        (RESOURCE_WOOD.to_string(), 1),
-    ])
+    ResourceCollection::from([(Resource::Clay, 1), (Resource::Wood, 1)])
 }
 
 fn get_village_cost() -> ResourceCollection {
     ResourceCollection::from([
-        (RESOURCE_CLAY.to_string(), 1),
-        (RESOURCE_WOOD.to_string(), 1),
-        (RESOURCE_SHEEP.to_string(), 1),
-        (RESOURCE_WHEAT.to_string(), 1),
+        (Resource::Clay, 1),
+        (Resource::Wood, 1),
+        (Resource::Sheep, 1),
+        (Resource::Wheat, 1),
     ])
 }
 
 fn get_city_cost() -> ResourceCollection {
-    ResourceCollection::from([
-        (RESOURCE_ORE.to_string(), 3),
-        (RESOURCE_WHEAT.to_string(), 2),
-    ])
+    ResourceCollection::from([(Resource::Ore, 3), (Resource::Wheat, 2)])
 }
 
 fn get_development_card_cost() -> ResourceCollection {
     ResourceCollection::from([
-        (RESOURCE_ORE.to_string(), 1),
-        (RESOURCE_SHEEP.to_string(), 1),
-        (RESOURCE_WHEAT.to_string(), 1),
+        (Resource::Ore, 1),
+        (Resource::Sheep, 1),
+        (Resource::Wheat, 1),
     ])
 }
 
-fn remove_resources(
-    player: &mut RefMut<'_, Player>,
-    resources: &ResourceCollection,
+pub fn check_player_piece(
+    game: &Game,
+    player_id: &PlayerId,
+    piece_type: &PieceType,
 ) -> GameActionResult {
-    if !player.get_resources().has_resources(&resources) {
-        return Err(GameError::NotEnoughResources);
-    }
-
-    if !player.get_resources_mut().remove_resources(&resources) {
-        error!(
-            "Failed to remove resources {:?} from {}",
-            resources,
-            player.get_id()
-        );
-        return Err(GameError::ActionFailed);
+    if !game
+        .get_player(*player_id)
+        .borrow()
+        .get_pieces()
+        .has_piece(piece_type)
+    {
+        trace!("Player {} has no {:?} pieces left", player_id, piece_type);
+        return Err(GameError::NoPiecesLeft);
     }
     Ok(())
 }
 
-pub fn place_road(
-    settlement_map: &mut SettlementMap,
+fn can_buy_piece(
+    game: &Game,
+    player_id: &PlayerId,
+    piece_type: &PieceType,
+    resource_cost: ResourceCollection,
+) -> bool {
+    let player = game.get_player(*player_id).borrow();
+    player.get_pieces().has_piece(piece_type)
+        && player.get_resources().has_resources(&resource_cost)
+}
+
+/// Moves the resources from the player to the bank
+fn get_payment(player_id: &PlayerId, resources: ResourceCollection) -> ResourceTransaction {
+    let mut payment = ResourceTransaction::new();
+    payment.transfer(
+        ResourceAccount::Player(*player_id),
+        ResourceAccount::Bank,
+        resources,
+    );
+    payment
+}
+
+/// Checks that the road is free and connected to a settlement or road of the player
+pub fn check_road_placement(
+    settlement_map: &SettlementMap,
     road_id: &String,
     player_id: &PlayerId,
 ) -> Result<(), GameError> {
     let road = match settlement_map.get_road(road_id) {
         None => {
-            error!("Road \"{}\" not found", road_id);
-            return Err(GameError::ActionFailed);
+            trace!("Road \"{}\" not found", road_id);
+            return Err(GameError::InvalidLocation);
         }
         Some(road) => road,
     };
@@ -260,14 +380,26 @@ pub fn place_road(
         Some(settlement) => settlement,
     };
 
-    if !settlement_a.is_owner(&player_id) && !settlement_b.is_owner(&player_id) {
+    if !settlement_a.is_owner(player_id)
+        && !settlement_b.is_owner(player_id)
+        && !settlement_map.is_road_connected_to_player_road(road_id, player_id)
+    {
         trace!(
-            "Player {} does not own a settlement on road \"{}\"",
+            "Player {} does not own a settlement or road next to road \"{}\"",
             player_id,
             road_id
         );
         return Err(GameError::InvalidLocation);
     }
+    Ok(())
+}
+
+pub fn place_road(
+    settlement_map: &mut SettlementMap,
+    road_id: &String,
+    player_id: &PlayerId,
+) -> Result<(), GameError> {
+    check_road_placement(settlement_map, road_id, player_id)?;
 
     match settlement_map
         .get_road_mut(road_id)
@@ -282,12 +414,37 @@ pub fn place_road(
     }
 }
 
-fn place_settlement(
-    settlement_map: &mut SettlementMap,
+/// Roads the player can build regardless of pieces and resources
+pub fn get_buildable_road_ids(
+    settlement_map: &SettlementMap,
+    player_id: &PlayerId,
+) -> Vec<RoadLocationId> {
+    let mut road_ids: Vec<RoadLocationId> = settlement_map
+        .get_roads()
+        .into_iter()
+        .map(|road| road.get_id())
+        .filter(|road_id| check_road_placement(settlement_map, road_id, player_id).is_ok())
+        .cloned()
+        .collect();
+    road_ids.sort();
+    road_ids
+}
+
+/// Checks the distance rule, the road connection and that the location can hold the settlement type
+fn check_settlement_placement(
+    settlement_map: &SettlementMap,
     settlement_id: &String,
-    settlement_type: SettlementType,
-    player_id: &usize,
+    settlement_type: &SettlementType,
+    player_id: &PlayerId,
 ) -> Result<(), GameError> {
+    let settlement = match settlement_map.get_settlement(settlement_id) {
+        Some(settlement) => settlement,
+        None => {
+            trace!("Settlement \"{}\" not found", settlement_id);
+            return Err(GameError::InvalidLocation);
+        }
+    };
+
     if SettlementMap::any_settlement_occupied(
         &(settlement_map.get_neighbor_settlements(settlement_id)),
     ) {
@@ -303,16 +460,31 @@ fn place_settlement(
         return Err(GameError::InvalidLocation);
     }
 
-    let settlement: &mut SettlementLocation =
-        match settlement_map.get_settlement_mut(&settlement_id) {
-            Some(settlement) => settlement,
-            None => {
-                trace!("Settlement \"{}\" not found", settlement_id);
-                return Err(GameError::InvalidLocation);
-            }
-        };
+    if !settlement.can_build_settlement(settlement_type, player_id) {
+        trace!(
+            "Player {} cannot build a {:?} at {}",
+            player_id,
+            settlement_type,
+            settlement_id
+        );
+        return Err(GameError::InvalidLocation);
+    }
+    Ok(())
+}
+
+fn place_settlement(
+    settlement_map: &mut SettlementMap,
+    settlement_id: &String,
+    settlement_type: SettlementType,
+    player_id: &usize,
+) -> Result<(), GameError> {
+    check_settlement_placement(settlement_map, settlement_id, &settlement_type, player_id)?;
 
-    match settlement.build_settlement(settlement_type, player_id) {
+    match settlement_map
+        .get_settlement_mut(settlement_id)
+        .unwrap()
+        .build_settlement(settlement_type, player_id)
+    {
         Ok(_) => Ok(()),
         Err(err) => {
             trace!("Failed to build village \"{}\"", err);
@@ -320,3 +492,23 @@ fn place_settlement(
         }
     }
 }
+
+/// Settlements the player can build regardless of pieces and resources
+fn get_buildable_settlement_ids(
+    settlement_map: &SettlementMap,
+    settlement_type: &SettlementType,
+    player_id: &PlayerId,
+) -> Vec<SettlementLocationId> {
+    let mut settlement_ids: Vec<SettlementLocationId> = settlement_map
+        .get_settlements()
+        .into_iter()
+        .map(|settlement| settlement.get_id())
+        .filter(|settlement_id| {
+            check_settlement_placement(settlement_map, settlement_id, settlement_type, player_id)
+                .is_ok()
+        })
+        .cloned()
+        .collect();
+    settlement_ids.sort();
+    settlement_ids
+}
@@ -4,15 +4,20 @@ use settler_island_util_derive::HasStateId;
 
 use crate::game::{
     board::location::{
-        road_location::PlayerRoad,
+        road_location::{PlayerRoad, RoadLocationId},
         settlement_location::{SettlementLocation, SettlementType},
         settlement_map::SettlementMap,
     },
+    event::GameEvent,
+    piece::PieceType,
     player::{Player, PlayerId},
     state::{
-        action_data::{PlaceSettlementData, BUILD_ROAD_ACTION},
-        state_machine::{GameAction, GameState, StateMachine},
-        states::{roll_dice::RollDice, start_village_placement::StartVillagePlacement},
+        action_data::{GameActionKind, PlaceRoadData},
+        legal_action::LegalAction,
+        state_machine::{GameState, StateMachine},
+        states::{
+            roll_dice::RollDice, select_action, start_village_placement::StartVillagePlacement,
+        },
     },
     Game, GameError,
 };
@@ -29,63 +34,77 @@ impl StartRoadPlacement {
         &self,
         game: &mut Game,
         player_id: &PlayerId,
-        action_data: Value,
+        place_road_data: PlaceRoadData,
     ) -> Result<(), GameError> {
-        let place_road_data = match serde_json::from_value::<PlaceSettlementData>(action_data) {
-            Ok(data) => data,
-            Err(err) => {
-                trace!("Failed to parse place village data \"{}\"", err.to_string());
-                return Err(GameError::ActionDataInvalid);
-            }
-        };
-
+        select_action::check_player_piece(game, player_id, &PieceType::Road)?;
         if let Err(err) = StartRoadPlacement::place_road(
             game.get_board_mut().get_settlement_map_mut(),
-            &place_road_data.settlement_id,
+            &place_road_data.road_id,
             player_id,
         ) {
             return Err(err);
         }
-
-        // end turn on settlement placed
-        // last player can place all settlements in one round
-        let has_two_settlements =
-            StartRoadPlacement::get_player_settlement_count(game, player_id) >= 2;
+        game.get_player(*player_id)
+            .borrow_mut()
+            .get_pieces_mut()
+            .take_piece(&PieceType::Road);
+        game.add_event(GameEvent::RoadBuilt {
+            player_id: *player_id,
+            road_id: place_road_data.road_id,
+        });
+
+        // first village and road are placed in player order, the second ones in reverse order.
+        // The last player places both in a row and the first player starts the game
+        let settlement_count = StartRoadPlacement::get_player_settlement_count(game, player_id);
+        let is_first_player = *game.get_current_player_index() == 0;
         let is_last_player = *game.get_current_player_index() == (game.get_player_count() - 1);
-        if !is_last_player {
-            game.end_turn();
-        } else if has_two_settlements {
-            game.end_turn();
-        }
-
-        // If all players placed their settlements and roads the game continues with the first dice roll
-        let all_players_placed_two_settlements =
-            StartRoadPlacement::all_players_have_at_least_n_settlements(game, 2);
-        if all_players_placed_two_settlements {
-            if let Err(_) = game
-                .get_state_machine()
-                .borrow_mut()
-                .transition_to(game, RollDice::get_id())
-            {
-                return Err(GameError::ActionFailed);
+        let next_state_id = match settlement_count {
+            1 if is_last_player => StartVillagePlacement::get_id(),
+            1 => {
+                game.end_turn();
+                StartVillagePlacement::get_id()
             }
-        } else {
-            if let Err(_) = game
-                .get_state_machine()
-                .borrow_mut()
-                .transition_to(game, StartVillagePlacement::get_id())
-            {
-                return Err(GameError::ActionFailed);
+            _ if is_first_player => RollDice::get_id(),
+            _ => {
+                game.end_turn_reverse();
+                StartVillagePlacement::get_id()
             }
-        }
+        };
 
-        Ok(())
+        match game
+            .get_state_machine()
+            .borrow_mut()
+            .transition_to(game, next_state_id)
+        {
+            Err(_) => Err(GameError::ActionFailed),
+            Ok(_) => Ok(()),
+        }
     }
 
     fn place_road(
         settlement_map: &mut SettlementMap,
         road_id: &String,
         player_id: &PlayerId,
+    ) -> Result<(), GameError> {
+        StartRoadPlacement::check_road_placement(settlement_map, road_id, player_id)?;
+
+        match settlement_map
+            .get_road_mut(road_id)
+            .unwrap()
+            .build_road(PlayerRoad::from(*player_id))
+        {
+            Err(err) => {
+                trace!("Road {} placement failed \"{}\"", road_id, err);
+                Err(GameError::ActionFailed)
+            }
+            Ok(_) => Ok(()),
+        }
+    }
+
+    fn check_road_placement(
+        settlement_map: &SettlementMap,
+        road_id: &String,
+        player_id: &PlayerId,
     ) -> Result<(), GameError> {
         let road = match settlement_map.get_road(road_id) {
             None => {
@@ -124,26 +143,20 @@ impl StartRoadPlacement {
             Some(settlement) => settlement,
         };
 
-        if !settlement_a.is_owner(player_id) && !settlement_b.is_owner(player_id) {
+        // the road has to start at the village placed last which has no roads yet
+        let is_next_to_new_village = |settlement: &SettlementLocation| {
+            settlement.is_owner(player_id)
+                && !settlement_map.has_settlement_player_roads(settlement.get_id(), *player_id)
+        };
+        if !is_next_to_new_village(settlement_a) && !is_next_to_new_village(settlement_b) {
             trace!(
-                "Player {} does not own a settlement on road \"{}\"",
+                "Player {} does not own a new village on road \"{}\"",
                 player_id,
                 road_id
             );
             return Err(GameError::InvalidLocation);
         }
-
-        match settlement_map
-            .get_road_mut(road_id)
-            .unwrap()
-            .build_road(PlayerRoad::from(*player_id))
-        {
-            Err(err) => {
-                trace!("Road {} placement failed \"{}\"", road_id, err);
-                Err(GameError::ActionFailed)
-            }
-            Ok(_) => Ok(()),
-        }
+        Ok(())
     }
 
     fn get_player_settlement_count(game: &Game, player_id: &PlayerId) -> usize {
@@ -152,18 +165,6 @@ impl StartRoadPlacement {
             .get_player_settlements(player_id)
             .len()
     }
-
-    fn all_players_have_at_least_n_settlements(
-        game: &Game,
-        expected_settlement_count: usize,
-    ) -> bool {
-        game.get_players()
-            .iter()
-            .map(|player| {
-                StartRoadPlacement::get_player_settlement_count(game, &player.borrow().get_id())
-            })
-            .all(|settlement_count| settlement_count >= expected_settlement_count)
-    }
 }
 
 impl GameState for StartRoadPlacement {
@@ -171,21 +172,60 @@ impl GameState for StartRoadPlacement {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> Result<(), GameError> {
         if !game.is_player_turn(player_id) {
             return Err(GameError::NotPlayerTurn);
         }
 
-        match action.id.as_str() {
-            BUILD_ROAD_ACTION => self.process_place_road(game, player_id, action.data),
-            _ => Err(GameError::ActionNotAllowed),
+        match action {
+            GameActionKind::BuildRoad(place_road_data) => {
+                self.process_place_road(game, player_id, place_road_data)
+            }
+            GameActionKind::BuyDevelopmentCard
+            | GameActionKind::PlayDevelopmentCard(_)
+            | GameActionKind::BuildSettlement(_)
+            | GameActionKind::PlaceRobber(_)
+            | GameActionKind::RemoveCards(_)
+            | GameActionKind::RollDice
+            | GameActionKind::EndTurn
+            | GameActionKind::OfferTrade(_)
+            | GameActionKind::OfferBankTrade(_)
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(GameError::ActionNotAllowed),
+        }
+    }
+
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        if !game.is_player_turn(player_id)
+            || select_action::check_player_piece(game, player_id, &PieceType::Road).is_err()
+        {
+            return vec![];
         }
+
+        let settlement_map = game.get_board().get_settlement_map();
+        let mut road_ids: Vec<RoadLocationId> = settlement_map
+            .get_roads()
+            .into_iter()
+            .map(|road| road.get_id())
+            .filter(|road_id| {
+                StartRoadPlacement::check_road_placement(settlement_map, road_id, player_id).is_ok()
+            })
+            .cloned()
+            .collect();
+        road_ids.sort();
+        vec![LegalAction::BuildRoad { road_ids }]
     }
 
-    fn get_state(&self) -> Option<Value> {
+    fn get_state(&self, game: &Game) -> Option<Value> {
         None
     }
 
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![StartVillagePlacement::get_id(), RollDice::get_id()]
+    }
+
     fn activate(&mut self, game: &Game) {}
 }
@@ -1,18 +1,26 @@
-use log::trace;
+use log::{error, trace};
 use serde_json::Value;
 use settler_island_util_derive::HasStateId;
 
 use crate::game::{
-    board::location::{
-        settlement_location::{SettlementLocation, SettlementType},
-        settlement_map::SettlementMap,
+    board::{
+        hexagon::hexagon_tile::TileType,
+        location::{
+            settlement_location::{SettlementLocation, SettlementLocationId, SettlementType},
+            settlement_map::SettlementMap,
+        },
+        resource::player_resources::ResourceCollection,
     },
+    event::GameEvent,
+    piece::PieceType,
     player::{Player, PlayerId},
     state::{
-        action_data::{PlaceSettlementData, BUILD_SETTLEMENT_ACTION},
-        state_machine::{GameAction, GameState, StateMachine},
-        states::start_road_placement::StartRoadPlacement,
+        action_data::{GameActionKind, PlaceSettlementData},
+        legal_action::LegalAction,
+        state_machine::{GameState, StateMachine},
+        states::{select_action, start_road_placement::StartRoadPlacement},
     },
+    transaction::{ResourceAccount, ResourceTransaction},
     Game, GameError,
 };
 
@@ -28,22 +36,51 @@ impl StartVillagePlacement {
         &self,
         game: &mut Game,
         player_id: &PlayerId,
-        action_data: Value,
+        place_village_data: PlaceSettlementData,
     ) -> Result<(), GameError> {
-        let place_village_data = match serde_json::from_value::<PlaceSettlementData>(action_data) {
-            Ok(data) => data,
-            Err(err) => {
-                trace!("Failed to parse place village data \"{}\"", err.to_string());
-                return Err(GameError::ActionDataInvalid);
-            }
-        };
+        if !matches!(place_village_data.settlement_type, SettlementType::Village) {
+            trace!(
+                "Only villages can be placed during setup, not {:?}",
+                place_village_data.settlement_type
+            );
+            return Err(GameError::ActionDataInvalid);
+        }
+        select_action::check_player_piece(game, player_id, &PieceType::Village)?;
+
+        // the payment is validated before the village is placed so a failure changes nothing
+        let starting_payment =
+            if StartVillagePlacement::get_player_settlement_count(game, player_id) == 1 {
+                let starting_payment = StartVillagePlacement::get_starting_payment(
+                    game,
+                    player_id,
+                    &place_village_data.settlement_id,
+                )?;
+                starting_payment.validate(game)?;
+                Some(starting_payment)
+            } else {
+                None
+            };
 
-        if let Err(err) = StartVillagePlacement::place_village(
+        StartVillagePlacement::place_village(
             game.get_board_mut().get_settlement_map_mut(),
             &place_village_data.settlement_id,
             player_id,
-        ) {
-            return Err(err);
+        )?;
+        game.get_player(*player_id)
+            .borrow_mut()
+            .get_pieces_mut()
+            .take_piece(&PieceType::Village);
+        game.add_event(GameEvent::SettlementBuilt {
+            player_id: *player_id,
+            settlement_id: place_village_data.settlement_id.clone(),
+        });
+
+        if let Some(starting_payment) = starting_payment {
+            starting_payment.commit(game)?;
+            let resources = starting_payment.get_received_resources();
+            if !resources.is_empty() {
+                game.add_event(GameEvent::ResourcesProduced { resources });
+            }
         }
 
         if let Err(err) = game
@@ -61,12 +98,7 @@ impl StartVillagePlacement {
         settlement_id: &String,
         player_id: &usize,
     ) -> Result<(), GameError> {
-        if SettlementMap::any_settlement_occupied(
-            &(settlement_map.get_neighbor_settlements(settlement_id)),
-        ) {
-            trace!("Settlement cannot placed next to a settlement");
-            return Err(GameError::InvalidLocation);
-        }
+        StartVillagePlacement::check_village_placement(settlement_map, settlement_id)?;
 
         let settlement: &mut SettlementLocation =
             match settlement_map.get_settlement_mut(&settlement_id) {
@@ -86,6 +118,70 @@ impl StartVillagePlacement {
         }
     }
 
+    /// Starting villages only need a free location which follows the distance rule
+    fn check_village_placement(
+        settlement_map: &SettlementMap,
+        settlement_id: &String,
+    ) -> Result<(), GameError> {
+        if SettlementMap::any_settlement_occupied(
+            &(settlement_map.get_neighbor_settlements(settlement_id)),
+        ) {
+            trace!("Settlement cannot placed next to a settlement");
+            return Err(GameError::InvalidLocation);
+        }
+
+        match settlement_map.get_settlement(settlement_id) {
+            None => {
+                trace!("Settlement \"{}\" not found", settlement_id);
+                Err(GameError::InvalidLocation)
+            }
+            Some(settlement) if settlement.get_settlement().is_some() => {
+                trace!("Settlement \"{}\" is already occupied", settlement_id);
+                Err(GameError::InvalidLocation)
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// The second village yields one resource of every neighbor tile
+    fn get_starting_payment(
+        game: &Game,
+        player_id: &PlayerId,
+        settlement_id: &String,
+    ) -> Result<ResourceTransaction, GameError> {
+        let mut starting_resources = ResourceCollection::new();
+        let board = game.get_board();
+        let settlement = match board.get_settlement_map().get_settlement(settlement_id) {
+            None => {
+                error!("Settlement \"{}\" not found", settlement_id);
+                return Err(GameError::ActionFailed);
+            }
+            Some(settlement) => settlement,
+        };
+        for tile_coordinates in settlement.get_neighbor_tiles() {
+            let tile = match board.get_tile_map().get_tile(tile_coordinates) {
+                None => continue,
+                Some(tile) => tile.borrow(),
+            };
+            if let TileType::ResourceTile(resource) = tile.get_type() {
+                starting_resources[*resource] += 1;
+            }
+        }
+
+        trace!(
+            "Player {} receives starting resources {:?}",
+            player_id,
+            starting_resources
+        );
+        let mut starting_payment = ResourceTransaction::new();
+        starting_payment.transfer(
+            ResourceAccount::Bank,
+            ResourceAccount::Player(*player_id),
+            starting_resources,
+        );
+        Ok(starting_payment)
+    }
+
     fn get_player_settlement_count(game: &Game, player_id: &PlayerId) -> usize {
         game.get_board()
             .get_settlement_map()
@@ -111,21 +207,182 @@ impl GameState for StartVillagePlacement {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> Result<(), GameError> {
         if !game.is_player_turn(player_id) {
             return Err(GameError::NotPlayerTurn);
         }
 
-        match action.id.as_str() {
-            BUILD_SETTLEMENT_ACTION => self.process_place_village(game, player_id, action.data),
-            _ => Err(GameError::ActionNotAllowed),
+        match action {
+            GameActionKind::BuildSettlement(place_village_data) => {
+                self.process_place_village(game, player_id, place_village_data)
+            }
+            GameActionKind::BuyDevelopmentCard
+            | GameActionKind::PlayDevelopmentCard(_)
+            | GameActionKind::BuildRoad(_)
+            | GameActionKind::PlaceRobber(_)
+            | GameActionKind::RemoveCards(_)
+            | GameActionKind::RollDice
+            | GameActionKind::EndTurn
+            | GameActionKind::OfferTrade(_)
+            | GameActionKind::OfferBankTrade(_)
+            | GameActionKind::AcceptTrade
+            | GameActionKind::RejectTrade
+            | GameActionKind::CompleteTrade(_)
+            | GameActionKind::CancelTrade => Err(GameError::ActionNotAllowed),
+        }
+    }
+
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        if !game.is_player_turn(player_id)
+            || select_action::check_player_piece(game, player_id, &PieceType::Village).is_err()
+        {
+            return vec![];
         }
+
+        let settlement_map = game.get_board().get_settlement_map();
+        let mut settlement_ids: Vec<SettlementLocationId> = settlement_map
+            .get_settlements()
+            .into_iter()
+            .map(|settlement| settlement.get_id())
+            .filter(|settlement_id| {
+                StartVillagePlacement::check_village_placement(settlement_map, settlement_id)
+                    .is_ok()
+            })
+            .cloned()
+            .collect();
+        settlement_ids.sort();
+        vec![LegalAction::BuildSettlement {
+            settlement_type: SettlementType::Village,
+            settlement_ids,
+        }]
     }
 
-    fn get_state(&self) -> Option<Value> {
+    fn get_state(&self, game: &Game) -> Option<Value> {
         None
     }
 
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![StartRoadPlacement::get_id()]
+    }
+
     fn activate(&mut self, game: &Game) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::{
+            location::settlement_location::{SettlementLocationId, SettlementType},
+            resource::{bank::Bank, player_resources::ResourceCollection},
+        },
+        piece::PieceType,
+        player::PlayerId,
+        state::{
+            action_data::{GameActionKind, PlaceSettlementData},
+            state_machine::GameState,
+        },
+        test_util::{generate_game, play_until},
+        Game, GameError,
+    };
+
+    use super::StartVillagePlacement;
+
+    fn get_current_player_id(game: &Game) -> PlayerId {
+        *game.get_current_player().borrow().get_id()
+    }
+
+    fn is_settlement_free(game: &Game, settlement_id: &SettlementLocationId) -> bool {
+        game.get_board()
+            .get_settlement_map()
+            .get_settlement(settlement_id)
+            .unwrap()
+            .get_settlement()
+            .is_none()
+    }
+
+    /// Free locations of the current player which yield starting resources
+    fn get_productive_settlement_id(game: &Game) -> SettlementLocationId {
+        let player_id = get_current_player_id(game);
+        game.get_board()
+            .get_settlement_map()
+            .get_settlements()
+            .into_iter()
+            .map(|settlement| settlement.get_id().clone())
+            .find(|settlement_id| {
+                StartVillagePlacement::check_village_placement(
+                    game.get_board().get_settlement_map(),
+                    settlement_id,
+                )
+                .is_ok()
+                    && !StartVillagePlacement::get_starting_payment(game, &player_id, settlement_id)
+                        .unwrap()
+                        .get_received_resources()
+                        .is_empty()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn only_villages_can_be_placed_during_setup() {
+        let mut game = generate_game(3, 7);
+        let player_id = get_current_player_id(&game);
+        let settlement_id = get_productive_settlement_id(&game);
+
+        let action_result = game.perform_action(
+            &player_id,
+            GameActionKind::BuildSettlement(PlaceSettlementData {
+                settlement_type: SettlementType::City,
+                settlement_id: settlement_id.clone(),
+            }),
+        );
+
+        assert_eq!(action_result, Err(GameError::ActionDataInvalid));
+        assert!(is_settlement_free(&game, &settlement_id));
+        assert!(game.get_recorded_actions().is_empty());
+    }
+
+    #[test]
+    fn failed_starting_payment_leaves_no_change() {
+        let mut game = generate_game(3, 7);
+        assert!(play_until(&mut game, 100, |game| {
+            game.get_state_machine()
+                .borrow()
+                .get_current_state()
+                .get_id()
+                == StartVillagePlacement::get_id()
+                && StartVillagePlacement::get_player_settlement_count(
+                    game,
+                    &get_current_player_id(game),
+                ) == 1
+        }));
+        let player_id = get_current_player_id(&game);
+        let settlement_id = get_productive_settlement_id(&game);
+        *game.get_board_mut().get_bank_mut() = Bank::from_resources(ResourceCollection::new());
+        let village_count = game
+            .get_player(player_id)
+            .borrow()
+            .get_pieces()
+            .get_piece_count(&PieceType::Village);
+        let event_count = game.get_events().len();
+
+        let action_result = game.perform_action(
+            &player_id,
+            GameActionKind::BuildSettlement(PlaceSettlementData {
+                settlement_type: SettlementType::Village,
+                settlement_id: settlement_id.clone(),
+            }),
+        );
+
+        assert_eq!(action_result, Err(GameError::NotEnoughBankResources));
+        assert!(is_settlement_free(&game, &settlement_id));
+        assert_eq!(
+            game.get_player(player_id)
+                .borrow()
+                .get_pieces()
+                .get_piece_count(&PieceType::Village),
+            village_count
+        );
+        assert_eq!(game.get_events().len(), event_count);
+    }
+}
@@ -1,23 +1,28 @@
-use std::{borrow::Borrow, cell::RefCell};
+use std::{borrow::Borrow, cell::RefCell, sync::Arc};
 
 use log::{error, trace};
-use serde_json::Value;
+use serde_json::{json, Value};
 use settler_island_util_derive::HasStateId;
 
 use crate::game::{
+    board::{
+        resource::{base_resource::Resource, player_resources::ResourceCollection},
+        trade_contract::{AcceptsNAnyTradeContract, TradeContract},
+    },
+    event::GameEvent,
     player::{Player, PlayerId},
     state::{
-        action_data::{
-            CompletePlayerTradeData, TradeOfferData, ACCEPT_TRADE_ACTION, CANCEL_TRADE_ACTION,
-            COMPLETE_TRADE_ACTION, OFFER_BANK_TRADE_ACTION, OFFER_TRADE_ACTION,
-            REJECT_TRADE_ACTION,
-        },
-        state_machine::{GameAction, GameActionResult, GameState, StateMachine},
+        action_data::{CompletePlayerTradeData, GameActionKind, TradeOfferData},
+        legal_action::{BankTradeOption, LegalAction},
+        state_machine::{GameActionResult, GameState, StateMachine},
     },
     trade::TradeOffer,
+    transaction::{ResourceAccount, ResourceTransaction},
     Game, GameError,
 };
 
+use super::select_action::SelectAction;
+
 #[derive(HasStateId)]
 pub struct Trading {}
 
@@ -32,25 +37,91 @@ impl GameState for Trading {
         &mut self,
         game: &mut Game,
         player_id: &PlayerId,
-        action: GameAction,
+        action: GameActionKind,
     ) -> GameActionResult {
         if game.is_player_turn(player_id) {
-            match action.id.as_str() {
-                COMPLETE_TRADE_ACTION => process_complete_trade(game, action.data),
-                CANCEL_TRADE_ACTION => process_cancel_trade(game),
-                _ => Err(GameError::ActionNotAllowed),
+            match action {
+                GameActionKind::CompleteTrade(complete_data) => {
+                    process_complete_trade(game, complete_data)
+                }
+                GameActionKind::CancelTrade => process_cancel_trade(game),
+                GameActionKind::BuyDevelopmentCard
+                | GameActionKind::PlayDevelopmentCard(_)
+                | GameActionKind::BuildSettlement(_)
+                | GameActionKind::BuildRoad(_)
+                | GameActionKind::PlaceRobber(_)
+                | GameActionKind::RemoveCards(_)
+                | GameActionKind::RollDice
+                | GameActionKind::EndTurn
+                | GameActionKind::OfferTrade(_)
+                | GameActionKind::OfferBankTrade(_)
+                | GameActionKind::AcceptTrade
+                | GameActionKind::RejectTrade => Err(GameError::ActionNotAllowed),
+            }
+        } else {
+            match action {
+                GameActionKind::AcceptTrade => process_accept_trade_offer(game, *player_id),
+                GameActionKind::RejectTrade => process_reject_trade_offer(game, *player_id),
+                GameActionKind::BuyDevelopmentCard
+                | GameActionKind::PlayDevelopmentCard(_)
+                | GameActionKind::BuildSettlement(_)
+                | GameActionKind::BuildRoad(_)
+                | GameActionKind::PlaceRobber(_)
+                | GameActionKind::RemoveCards(_)
+                | GameActionKind::RollDice
+                | GameActionKind::EndTurn
+                | GameActionKind::OfferTrade(_)
+                | GameActionKind::OfferBankTrade(_)
+                | GameActionKind::CompleteTrade(_)
+                | GameActionKind::CancelTrade => Err(GameError::ActionNotAllowed),
+            }
+        }
+    }
+
+    fn get_legal_actions(&self, game: &Game, player_id: &PlayerId) -> Vec<LegalAction> {
+        let opt_offer = game.get_trade_offer().borrow();
+        let offer = match opt_offer.as_ref() {
+            None => return vec![],
+            Some(offer) => offer,
+        };
+
+        if game.is_player_turn(player_id) {
+            let accepted_player_ids: Vec<PlayerId> = offer
+                .players_accepted
+                .iter()
+                .filter(|(_, accepted)| **accepted)
+                .map(|(accepted_player_id, _)| *accepted_player_id)
+                .collect();
+
+            let mut legal_actions = vec![];
+            if !accepted_player_ids.is_empty() {
+                legal_actions.push(LegalAction::CompleteTrade {
+                    accepted_player_ids,
+                });
             }
+            legal_actions.push(LegalAction::CancelTrade);
+            legal_actions
         } else {
-            match action.id.as_str() {
-                ACCEPT_TRADE_ACTION => process_accept_trade_offer(game, *player_id),
-                REJECT_TRADE_ACTION => process_reject_trade_offer(game, *player_id),
-                _ => Err(GameError::ActionNotAllowed),
+            let mut legal_actions = vec![];
+            if game
+                .get_player(*player_id)
+                .borrow()
+                .get_resources()
+                .has_resources(&offer.resource_receive)
+            {
+                legal_actions.push(LegalAction::AcceptTrade);
             }
+            legal_actions.push(LegalAction::RejectTrade);
+            legal_actions
         }
     }
 
-    fn get_state(&self) -> Option<serde_json::Value> {
-        None
+    fn get_state(&self, game: &Game) -> Option<serde_json::Value> {
+        Some(json!({ "trade_offer": *game.get_trade_offer().borrow() }))
+    }
+
+    fn get_transition_ids(&self) -> Vec<StateId> {
+        vec![SelectAction::get_id()]
     }
 
     fn activate(&mut self, game: &Game) {}
@@ -59,16 +130,9 @@ impl GameState for Trading {
 pub fn process_trade_offer(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    trade_data: TradeOfferData,
 ) -> Result<(), GameError> {
-    let trade_data = match serde_json::from_value::<TradeOfferData>(action_data) {
-        Err(err) => {
-            trace!("Failed to parse trade offer data \"{}\"", err.to_string());
-            return Err(GameError::ActionDataInvalid);
-        }
-        Ok(data) => data,
-    };
-
+    check_trade_data(&trade_data)?;
     if !game
         .get_player(*player_id)
         .borrow()
@@ -89,30 +153,169 @@ pub fn process_trade_offer(
             .collect(),
     ));
 
+    transition_to(game, Trading::get_id())
+}
+
+/// Trades need offered and received resources and cannot receive an offered resource type
+fn check_trade_data(trade_data: &TradeOfferData) -> Result<(), GameError> {
+    if trade_data.resource_offer.is_empty() || trade_data.resource_receive.is_empty() {
+        trace!("Trades need offered and received resources");
+        return Err(GameError::ActionDataInvalid);
+    }
+    if trade_data
+        .resource_offer
+        .iter()
+        .any(|(resource, _)| trade_data.resource_receive.get(&resource) > 0)
+    {
+        trace!("Trades cannot receive an offered resource type");
+        return Err(GameError::ActionDataInvalid);
+    }
     Ok(())
 }
 
 pub fn process_bank_trade_offer(
     game: &mut Game,
     player_id: &PlayerId,
-    action_data: Value,
+    trade_data: TradeOfferData,
 ) -> Result<(), GameError> {
-    error!("Bank trades are not implemented yet");
-    Err(GameError::ActionFailed)
-}
+    check_trade_data(&trade_data)?;
+
+    if !game
+        .get_player(*player_id)
+        .borrow()
+        .get_resources()
+        .has_resources(&trade_data.resource_offer)
+    {
+        return Err(GameError::NotEnoughResources);
+    }
+
+    // every offered resource type is traded at the best rate of the player
+    let trade_contracts = get_player_trade_contracts(game, player_id);
+    let mut receive_count = 0;
+    for (resource, offer_count) in trade_data.resource_offer.iter() {
+        let trade_contract = match get_best_trade_contract(&trade_contracts, &resource) {
+            None => {
+                error!(
+                    "Player {} has no trade contract for {:?}",
+                    player_id, resource
+                );
+                return Err(GameError::NoMatchingTradeContract);
+            }
+            Some(trade_contract) => trade_contract,
+        };
 
-fn process_complete_trade(game: &mut Game, action_data: Value) -> Result<(), GameError> {
-    let complete_data = match serde_json::from_value::<CompletePlayerTradeData>(action_data) {
-        Err(err) => {
+        if !offer_count.is_multiple_of(trade_contract.get_receive_count()) {
             trace!(
-                "Failed to parse complete trade data \"{}\"",
-                err.to_string()
+                "{} {:?} cannot be traded in bundles of {}",
+                offer_count,
+                resource,
+                trade_contract.get_receive_count()
             );
-            return Err(GameError::ActionDataInvalid);
+            return Err(GameError::NoMatchingTradeContract);
         }
-        Ok(data) => data,
-    };
+        receive_count +=
+            offer_count / trade_contract.get_receive_count() * trade_contract.get_send_count();
+    }
 
+    if trade_data.resource_receive.get_total() != receive_count {
+        trace!(
+            "Player {} requested {} resources but the best rate pays {}",
+            player_id,
+            trade_data.resource_receive.get_total(),
+            receive_count
+        );
+        return Err(GameError::NoMatchingTradeContract);
+    }
+
+    ResourceTransaction::new()
+        .transfer(
+            ResourceAccount::Player(*player_id),
+            ResourceAccount::Bank,
+            trade_data.resource_offer,
+        )
+        .transfer(
+            ResourceAccount::Bank,
+            ResourceAccount::Player(*player_id),
+            trade_data.resource_receive,
+        )
+        .commit(game)?;
+    game.add_event(GameEvent::TradeCompleted {
+        player_id: *player_id,
+        partner_id: None,
+        resource_offer: trade_data.resource_offer,
+        resource_receive: trade_data.resource_receive,
+    });
+    Ok(())
+}
+
+/// Bank trade contracts of the player ordered from best to worst exchange rate.
+/// Every player can trade 4:1 and additionally with the seaports next to their settlements
+pub fn get_player_trade_contracts(
+    game: &Game,
+    player_id: &PlayerId,
+) -> Vec<Arc<dyn TradeContract>> {
+    let mut trade_contracts: Vec<Arc<dyn TradeContract>> =
+        vec![Arc::new(AcceptsNAnyTradeContract::new(4, 1))];
+    trade_contracts.extend(
+        game.get_board()
+            .get_settlement_map()
+            .get_player_settlements(player_id)
+            .into_iter()
+            .filter_map(|settlement| settlement.get_seaport().as_ref())
+            .map(|seaport| seaport.get_trade_contract().clone()),
+    );
+
+    trade_contracts.sort_by(|a, b| {
+        (a.get_receive_count() * b.get_send_count())
+            .cmp(&(b.get_receive_count() * a.get_send_count()))
+    });
+    trade_contracts
+}
+
+/// Contract with the best exchange rate for the resource type,
+/// expects the contracts ordered by [get_player_trade_contracts]
+pub fn get_best_trade_contract(
+    trade_contracts: &[Arc<dyn TradeContract>],
+    resource: &Resource,
+) -> Option<Arc<dyn TradeContract>> {
+    trade_contracts
+        .iter()
+        .find(|contract| match contract.get_resource() {
+            None => true,
+            Some(contract_resource) => contract_resource == *resource,
+        })
+        .cloned()
+}
+
+/// Best exchange rate per resource if the player can afford a single trade
+pub fn get_bank_trade_options(game: &Game, player_id: &PlayerId) -> Vec<BankTradeOption> {
+    let trade_contracts = get_player_trade_contracts(game, player_id);
+    let player = game.get_player(*player_id).borrow();
+    Resource::ALL
+        .into_iter()
+        .filter_map(|resource| {
+            get_best_trade_contract(&trade_contracts, &resource)
+                .filter(|contract| {
+                    player
+                        .get_resources()
+                        .has_resources(&ResourceCollection::from([(
+                            resource,
+                            contract.get_receive_count(),
+                        )]))
+                })
+                .map(|contract| BankTradeOption {
+                    resource,
+                    offer_count: contract.get_receive_count(),
+                    receive_count: contract.get_send_count(),
+                })
+        })
+        .collect()
+}
+
+fn process_complete_trade(
+    game: &mut Game,
+    complete_data: CompletePlayerTradeData,
+) -> Result<(), GameError> {
     let opt_offer = game.get_trade_offer().borrow();
     let offer = match opt_offer.as_ref() {
         None => {
@@ -134,36 +337,49 @@ fn process_complete_trade(game: &mut Game, action_data: Value) -> Result<(), Gam
         return Err(GameError::ActionNotAllowed);
     }
 
-    let player = game.get_player(offer.creator);
-    let accepted_player = game.get_player(complete_data.accepted_player_id);
-    accepted_player
-        .borrow_mut()
-        .get_resources_mut()
-        .remove_resources(&offer.resource_receive);
-    player
-        .borrow_mut()
-        .get_resources_mut()
-        .remove_resources(&offer.resource_offer);
-
-    accepted_player
-        .borrow_mut()
-        .get_resources_mut()
-        .add_resources(offer.resource_offer.clone());
-    player
-        .borrow_mut()
-        .get_resources_mut()
-        .add_resources(offer.resource_receive.clone());
+    let mut trade = ResourceTransaction::new();
+    trade
+        .transfer(
+            ResourceAccount::Player(offer.creator),
+            ResourceAccount::Player(complete_data.accepted_player_id),
+            offer.resource_offer,
+        )
+        .transfer(
+            ResourceAccount::Player(complete_data.accepted_player_id),
+            ResourceAccount::Player(offer.creator),
+            offer.resource_receive,
+        );
+    let trade_completed = GameEvent::TradeCompleted {
+        player_id: offer.creator,
+        partner_id: Some(complete_data.accepted_player_id),
+        resource_offer: offer.resource_offer,
+        resource_receive: offer.resource_receive,
+    };
+    drop(opt_offer);
 
-    Ok(game.complete_trade_offer())
+    trade.commit(game)?;
+    game.complete_trade_offer();
+    game.add_event(trade_completed);
+    transition_to(game, SelectAction::get_id())
 }
 
 fn process_cancel_trade(game: &mut Game) -> Result<(), GameError> {
-    match game.get_trade_offer().borrow().as_ref() {
-        None => Err(GameError::ActionNotAllowed),
-        Some(_) => {
-            game.cancel_trade_offer();
-            Ok(())
-        }
+    if game.get_trade_offer().borrow().is_none() {
+        return Err(GameError::ActionNotAllowed);
+    }
+
+    game.cancel_trade_offer();
+    transition_to(game, SelectAction::get_id())
+}
+
+fn transition_to(game: &Game, state_id: StateId) -> Result<(), GameError> {
+    match game
+        .get_state_machine()
+        .borrow_mut()
+        .transition_to(game, state_id)
+    {
+        Err(_) => Err(GameError::ActionFailed),
+        Ok(_) => Ok(()),
     }
 }
 
@@ -206,3 +422,91 @@ fn process_reject_trade_offer(game: &mut Game, player_id: PlayerId) -> Result<()
     offer.players_accepted.insert(player_id, false);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::resource::{base_resource::Resource, player_resources::ResourceCollection},
+        player::PlayerId,
+        state::{
+            action_data::{GameActionKind, TradeOfferData},
+            states::select_action::SelectAction,
+        },
+        test_util::{generate_game, play_until},
+        Game, GameError,
+    };
+
+    /// Plays until [SelectAction] and gives the current player resources of every type
+    fn start_select_action() -> (Game, PlayerId) {
+        let mut game = generate_game(3, 13);
+        assert!(play_until(&mut game, 200, |game| {
+            game.get_state_machine()
+                .borrow()
+                .get_current_state_id()
+                .as_str()
+                == SelectAction::get_id()
+        }));
+
+        let player_id = *game.get_current_player_index();
+        game.get_player(player_id)
+            .borrow_mut()
+            .get_resources_mut()
+            .add_resources(ResourceCollection::from([
+                (Resource::Clay, 1),
+                (Resource::Ore, 1),
+                (Resource::Wood, 1),
+                (Resource::Wheat, 1),
+                (Resource::Sheep, 1),
+            ]));
+        (game, player_id)
+    }
+
+    fn offer_trade(
+        resource_offer: ResourceCollection,
+        resource_receive: ResourceCollection,
+    ) -> Result<(), GameError> {
+        let (mut game, player_id) = start_select_action();
+        let action_result = game.perform_action(
+            &player_id,
+            GameActionKind::OfferTrade(TradeOfferData {
+                resource_offer,
+                resource_receive,
+            }),
+        );
+        assert!(game.get_trade_offer().borrow().is_none());
+        action_result
+    }
+
+    #[test]
+    fn trade_offer_without_offered_resources_is_rejected() {
+        assert_eq!(
+            offer_trade(
+                ResourceCollection::new(),
+                ResourceCollection::from([(Resource::Ore, 1)])
+            ),
+            Err(GameError::ActionDataInvalid)
+        );
+    }
+
+    #[test]
+    fn trade_offer_without_received_resources_is_rejected() {
+        assert_eq!(
+            offer_trade(
+                ResourceCollection::from([(Resource::Clay, 1)]),
+                ResourceCollection::new()
+            ),
+            Err(GameError::ActionDataInvalid)
+        );
+    }
+
+    #[test]
+    fn trade_offer_receiving_an_offered_resource_is_rejected() {
+        assert_eq!(
+            offer_trade(
+                ResourceCollection::from([(Resource::Clay, 1), (Resource::Wood, 1)]),
+                ResourceCollection::from([(Resource::Clay, 1)])
+            ),
+            Err(GameError::ActionDataInvalid)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Helpers to set up and play games in tests
+
+use super::{
+    board::resource::player_resources::ResourceCollection,
+    color::Color,
+    piece::PieceInventory,
+    player::{Player, PlayerId},
+    state::{
+        action_data::{GameActionKind, PlaceRoadData, PlaceRobberData, PlaceSettlementData},
+        legal_action::LegalAction,
+    },
+    Game, GameSettings,
+};
+
+pub fn generate_game(player_count: usize, seed: u64) -> Game {
+    let settings = GameSettings {
+        players: player_count as u8,
+        seed,
+        ..Default::default()
+    };
+    let players = (0..player_count)
+        .map(|player_id| {
+            Player::from(
+                player_id,
+                Color::from(1.0, 0.0, 0.0, 1.0),
+                PieceInventory::default(),
+            )
+        })
+        .collect();
+    Game::generate(settings, players).unwrap()
+}
+
+/// First legal action of the first player who can act, trades and development cards are skipped
+pub fn get_next_action(game: &Game) -> Option<(PlayerId, GameActionKind)> {
+    for player_id in 0..game.get_player_count() {
+        for legal_action in game.get_legal_actions(&player_id) {
+            let action = match legal_action {
+                LegalAction::BuildSettlement {
+                    settlement_type,
+                    settlement_ids,
+                } if !settlement_ids.is_empty() => {
+                    GameActionKind::BuildSettlement(PlaceSettlementData {
+                        settlement_type,
+                        settlement_id: settlement_ids[settlement_ids.len() / 2].clone(),
+                    })
+                }
+                LegalAction::BuildRoad { road_ids } if !road_ids.is_empty() => {
+                    GameActionKind::BuildRoad(PlaceRoadData {
+                        road_id: road_ids[0].clone(),
+                    })
+                }
+                LegalAction::RemoveCards { count } => {
+                    let mut removed_cards = ResourceCollection::new();
+                    let mut count_left = count;
+                    let resources = game
+                        .get_player(player_id)
+                        .borrow()
+                        .get_resources()
+                        .get_resources();
+                    for (resource, resource_count) in resources.iter() {
+                        removed_cards[resource] = resource_count.min(count_left);
+                        count_left -= removed_cards[resource];
+                    }
+                    GameActionKind::RemoveCards(removed_cards)
+                }
+                LegalAction::PlaceRobber { targets } if !targets.is_empty() => {
+                    GameActionKind::PlaceRobber(PlaceRobberData {
+                        tile_location: targets[0].tile_location,
+                        robbed_player_id: targets[0].robbable_player_ids.first().copied(),
+                    })
+                }
+                LegalAction::RollDice => GameActionKind::RollDice,
+                LegalAction::EndTurn => GameActionKind::EndTurn,
+                _ => continue,
+            };
+            return Some((player_id, action));
+        }
+    }
+    None
+}
+
+/// Performs actions from [get_next_action] until `is_done` holds, returns false if it never did
+pub fn play_until(game: &mut Game, max_actions: usize, is_done: impl Fn(&Game) -> bool) -> bool {
+    for _ in 0..max_actions {
+        if is_done(game) {
+            return true;
+        }
+        match get_next_action(game) {
+            None => return false,
+            Some((player_id, action)) => game.perform_action(&player_id, action).unwrap(),
+        }
+    }
+    is_done(game)
+}
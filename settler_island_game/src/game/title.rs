@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use super::{player::PlayerId, state::states::development_card::DevelopmentCard, Game};
+
+pub const LONGEST_ROAD_MIN_LENGTH: usize = 5;
+pub const LARGEST_ARMY_MIN_KNIGHTS: usize = 3;
+
+/// Titles which are held by at most one player and grant bonus victory points
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BonusTitle {
+    LongestRoad,
+    LargestArmy,
+}
+
+impl BonusTitle {
+    pub fn get_victory_points(&self) -> usize {
+        match self {
+            BonusTitle::LongestRoad => 2,
+            BonusTitle::LargestArmy => 2,
+        }
+    }
+}
+
+/// Reassigns the longest road after a road or settlement was built.
+///
+/// The holder keeps the title on a tie. If the holder loses the longest road
+/// and several players share the new longest road nobody holds the title.
+pub fn update_longest_road(game: &mut Game) {
+    let settlement_map = game.get_board().get_settlement_map();
+    let road_lengths: HashMap<PlayerId, usize> = game
+        .get_players()
+        .iter()
+        .map(|player| *player.borrow().get_id())
+        .map(|player_id| {
+            (
+                player_id,
+                settlement_map.get_longest_road_length(&player_id),
+            )
+        })
+        .collect();
+
+    let holder_id = game.get_title_holder(&BonusTitle::LongestRoad).copied();
+    let new_holder_id = get_longest_road_holder(&road_lengths, holder_id);
+    if new_holder_id == holder_id {
+        return;
+    }
+
+    debug!(
+        "Longest road changed from {:?} to {:?}",
+        holder_id, new_holder_id
+    );
+    game.set_title_holder(BonusTitle::LongestRoad, new_holder_id);
+}
+
+fn get_longest_road_holder(
+    road_lengths: &HashMap<PlayerId, usize>,
+    holder_id: Option<PlayerId>,
+) -> Option<PlayerId> {
+    let longest_road_length = *road_lengths.values().max()?;
+    if longest_road_length < LONGEST_ROAD_MIN_LENGTH {
+        return None;
+    }
+
+    if let Some(holder_id) = holder_id {
+        if road_lengths.get(&holder_id) == Some(&longest_road_length) {
+            return Some(holder_id);
+        }
+    }
+
+    let mut longest_road_players = road_lengths
+        .iter()
+        .filter(|(_, length)| **length == longest_road_length)
+        .map(|(player_id, _)| *player_id);
+    match (longest_road_players.next(), longest_road_players.next()) {
+        (Some(player_id), None) => Some(player_id),
+        _ => None,
+    }
+}
+
+/// Reassigns the largest army after a knight was played.
+///
+/// Another player has to play strictly more knights than the holder to take the title.
+pub fn update_largest_army(game: &mut Game) {
+    let knight_counts: HashMap<PlayerId, usize> = game
+        .get_players()
+        .iter()
+        .map(|player| {
+            let player = player.borrow();
+            (
+                *player.get_id(),
+                player.get_played_development_card_count(DevelopmentCard::Knight),
+            )
+        })
+        .collect();
+
+    let holder_id = game.get_title_holder(&BonusTitle::LargestArmy).copied();
+    let new_holder_id = get_largest_army_holder(&knight_counts, holder_id);
+    if new_holder_id == holder_id {
+        return;
+    }
+
+    debug!(
+        "Largest army changed from {:?} to {:?}",
+        holder_id, new_holder_id
+    );
+    game.set_title_holder(BonusTitle::LargestArmy, new_holder_id);
+}
+
+fn get_largest_army_holder(
+    knight_counts: &HashMap<PlayerId, usize>,
+    holder_id: Option<PlayerId>,
+) -> Option<PlayerId> {
+    let holder_knight_count = holder_id
+        .and_then(|holder_id| knight_counts.get(&holder_id))
+        .copied()
+        .unwrap_or(0);
+
+    let largest_knight_count = knight_counts.values().max().copied().unwrap_or(0);
+    if largest_knight_count < LARGEST_ARMY_MIN_KNIGHTS
+        || largest_knight_count <= holder_knight_count
+    {
+        return holder_id;
+    }
+
+    let mut strongest_players = knight_counts
+        .iter()
+        .filter(|(_, knight_count)| **knight_count == largest_knight_count)
+        .map(|(player_id, _)| *player_id);
+    match (strongest_players.next(), strongest_players.next()) {
+        (Some(player_id), None) => Some(player_id),
+        _ => holder_id,
+    }
+}
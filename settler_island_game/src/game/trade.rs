@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 
 use super::{board::resource::player_resources::ResourceCollection, player::PlayerId};
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TradeOffer {
     pub creator: PlayerId,
     pub resource_offer: ResourceCollection,
     pub resource_receive: ResourceCollection,
-    pub players_accepted: HashMap<PlayerId, bool>,
+    pub players_accepted: BTreeMap<PlayerId, bool>,
 }
 
 impl TradeOffer {
@@ -16,7 +19,7 @@ impl TradeOffer {
         resource_receive: ResourceCollection,
         players: Vec<PlayerId>,
     ) -> Self {
-        let mut players_accepted = HashMap::<PlayerId, bool>::new();
+        let mut players_accepted = BTreeMap::<PlayerId, bool>::new();
         players.into_iter().for_each(|player_id| {
             if creator == player_id {
                 panic!("Cannot add creator as trade acceptee");
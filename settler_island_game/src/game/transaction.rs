@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap};
+
+use log::{error, trace};
+
+use super::{
+    board::resource::{base_resource::Resource, player_resources::ResourceCollection},
+    player::PlayerId,
+    Game, GameError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceAccount {
+    Bank,
+    Player(PlayerId),
+}
+
+#[derive(Debug, Clone)]
+struct ResourceTransfer {
+    from: ResourceAccount,
+    to: ResourceAccount,
+    resources: ResourceCollection,
+}
+
+/// Resource transfers between players and the bank which are applied all at once.
+/// Nothing changes if any account cannot pay its part
+#[derive(Debug, Clone, Default)]
+pub struct ResourceTransaction {
+    transfers: Vec<ResourceTransfer>,
+}
+
+impl ResourceTransaction {
+    pub fn new() -> Self {
+        ResourceTransaction { transfers: vec![] }
+    }
+
+    pub fn transfer(
+        &mut self,
+        from: ResourceAccount,
+        to: ResourceAccount,
+        resources: ResourceCollection,
+    ) -> &mut Self {
+        self.transfers.push(ResourceTransfer {
+            from,
+            to,
+            resources,
+        });
+        self
+    }
+
+    pub fn transfer_resource(
+        &mut self,
+        from: ResourceAccount,
+        to: ResourceAccount,
+        resource: &Resource,
+        count: usize,
+    ) -> &mut Self {
+        self.transfer(from, to, ResourceCollection::from([(*resource, count)]))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+
+    /// Checks that every account exists and owns enough resources for its net debit
+    pub fn validate(&self, game: &Game) -> Result<(), GameError> {
+        for (account, changes) in self.get_balance_changes() {
+            for (resource, change) in changes {
+                let balance = ResourceTransaction::get_balance(game, &account, &resource)?;
+                if (balance as i64) + change < 0 {
+                    trace!(
+                        "{:?} cannot pay {} {} with a balance of {}",
+                        account,
+                        -change,
+                        resource,
+                        balance
+                    );
+                    return Err(match account {
+                        ResourceAccount::Bank => GameError::NotEnoughBankResources,
+                        ResourceAccount::Player(_) => GameError::NotEnoughResources,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies all transfers or none of them
+    pub fn commit(&self, game: &mut Game) -> Result<(), GameError> {
+        self.validate(game)?;
+
+        for (account, changes) in self.get_balance_changes() {
+            let mut credits = ResourceCollection::new();
+            let mut debits = ResourceCollection::new();
+            for (resource, change) in changes {
+                match change {
+                    0 => (),
+                    change if change > 0 => {
+                        credits[resource] = change as usize;
+                    }
+                    change => {
+                        debits[resource] = (-change) as usize;
+                    }
+                }
+            }
+
+            let debited = match account {
+                ResourceAccount::Bank => {
+                    let bank = game.get_board_mut().get_bank_mut();
+                    bank.deposit_resources(credits);
+                    bank.withdraw_resources(&debits)
+                }
+                ResourceAccount::Player(player_id) => {
+                    let mut player = game.get_player(player_id).borrow_mut();
+                    player.get_resources_mut().add_resources(credits);
+                    player.get_resources_mut().remove_resources(&debits)
+                }
+            };
+            if !debited {
+                error!("Failed to debit {:?} from {:?}", debits, account);
+                return Err(GameError::ActionFailed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Net resources every player gains from the transaction
+    pub fn get_received_resources(&self) -> BTreeMap<PlayerId, ResourceCollection> {
+        let mut received_resources = BTreeMap::<PlayerId, ResourceCollection>::new();
+        for (account, changes) in self.get_balance_changes() {
+            let player_id = match account {
+                ResourceAccount::Bank => continue,
+                ResourceAccount::Player(player_id) => player_id,
+            };
+            for (resource, change) in changes {
+                if change > 0 {
+                    received_resources.entry(player_id).or_default()[resource] = change as usize;
+                }
+            }
+        }
+        received_resources
+    }
+
+    /// Resources each player pays in total, the bank is left out
+    pub fn get_paid_resources(&self) -> BTreeMap<PlayerId, ResourceCollection> {
+        let mut paid_resources = BTreeMap::<PlayerId, ResourceCollection>::new();
+        for (account, changes) in self.get_balance_changes() {
+            let player_id = match account {
+                ResourceAccount::Bank => continue,
+                ResourceAccount::Player(player_id) => player_id,
+            };
+            for (resource, change) in changes {
+                if change < 0 {
+                    paid_resources.entry(player_id).or_default()[resource] = (-change) as usize;
+                }
+            }
+        }
+        paid_resources
+    }
+
+    fn get_balance_changes(&self) -> HashMap<ResourceAccount, HashMap<Resource, i64>> {
+        let mut balance_changes = HashMap::<ResourceAccount, HashMap<Resource, i64>>::new();
+        for transfer in &self.transfers {
+            for (resource, count) in transfer.resources.iter() {
+                *balance_changes
+                    .entry(transfer.from)
+                    .or_default()
+                    .entry(resource)
+                    .or_insert(0) -= count as i64;
+                *balance_changes
+                    .entry(transfer.to)
+                    .or_default()
+                    .entry(resource)
+                    .or_insert(0) += count as i64;
+            }
+        }
+        balance_changes
+    }
+
+    fn get_balance(
+        game: &Game,
+        account: &ResourceAccount,
+        resource: &Resource,
+    ) -> Result<usize, GameError> {
+        match account {
+            ResourceAccount::Bank => Ok(game.get_board().get_bank().get_resource_count(resource)),
+            ResourceAccount::Player(player_id) => match game.get_players().get(*player_id) {
+                None => {
+                    error!("Player {} not found", player_id);
+                    Err(GameError::ActionFailed)
+                }
+                Some(player) => Ok(player.borrow().get_resources().get_resource(resource)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::resource::{base_resource::Resource, player_resources::ResourceCollection},
+        test_util::generate_game,
+        Game, GameError,
+    };
+
+    use super::{ResourceAccount, ResourceTransaction};
+
+    fn get_balances(game: &Game) -> (ResourceCollection, ResourceCollection, ResourceCollection) {
+        (
+            game.get_player(0).borrow().get_resources().get_resources(),
+            game.get_player(1).borrow().get_resources().get_resources(),
+            game.get_board().get_bank().get_resources().get_resources(),
+        )
+    }
+
+    #[test]
+    fn commit_moves_resources() {
+        let mut game = generate_game(2, 7);
+        let bank_wood = game
+            .get_board()
+            .get_bank()
+            .get_resource_count(&Resource::Wood);
+
+        ResourceTransaction::new()
+            .transfer_resource(
+                ResourceAccount::Bank,
+                ResourceAccount::Player(0),
+                &Resource::Wood,
+                2,
+            )
+            .commit(&mut game)
+            .unwrap();
+
+        assert_eq!(
+            game.get_player(0)
+                .borrow()
+                .get_resources()
+                .get_resource(&Resource::Wood),
+            2
+        );
+        assert_eq!(
+            game.get_board()
+                .get_bank()
+                .get_resource_count(&Resource::Wood),
+            bank_wood - 2
+        );
+    }
+
+    #[test]
+    fn failed_commit_leaves_no_change() {
+        let mut game = generate_game(2, 7);
+        ResourceTransaction::new()
+            .transfer_resource(
+                ResourceAccount::Bank,
+                ResourceAccount::Player(0),
+                &Resource::Wood,
+                2,
+            )
+            .commit(&mut game)
+            .unwrap();
+        let balances = get_balances(&game);
+
+        // player 0 can pay its part, player 1 owns nothing
+        let result = ResourceTransaction::new()
+            .transfer_resource(
+                ResourceAccount::Player(0),
+                ResourceAccount::Player(1),
+                &Resource::Wood,
+                2,
+            )
+            .transfer_resource(
+                ResourceAccount::Player(1),
+                ResourceAccount::Player(0),
+                &Resource::Ore,
+                1,
+            )
+            .commit(&mut game);
+
+        assert_eq!(result, Err(GameError::NotEnoughResources));
+        assert_eq!(get_balances(&game), balances);
+    }
+
+    #[test]
+    fn failed_bank_payout_leaves_no_change() {
+        let mut game = generate_game(2, 7);
+        let balances = get_balances(&game);
+        let bank_ore = game
+            .get_board()
+            .get_bank()
+            .get_resource_count(&Resource::Ore);
+
+        let result = ResourceTransaction::new()
+            .transfer_resource(
+                ResourceAccount::Bank,
+                ResourceAccount::Player(0),
+                &Resource::Wood,
+                1,
+            )
+            .transfer_resource(
+                ResourceAccount::Bank,
+                ResourceAccount::Player(1),
+                &Resource::Ore,
+                bank_ore + 1,
+            )
+            .commit(&mut game);
+
+        assert_eq!(result, Err(GameError::NotEnoughBankResources));
+        assert_eq!(get_balances(&game), balances);
+    }
+}
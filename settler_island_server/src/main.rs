@@ -54,6 +54,12 @@ async fn main() {
     info!("Listening on 8253");
 
     let game_server = GameServer::new();
+    if let Err(err) = game_server.lock().await.restore_lobbies().await {
+        error!("Failed to restore lobbies \"{}\"", err);
+    }
+    tokio::spawn(GameServer::close_abandoned_lobbies_after_grace_period(
+        game_server.clone(),
+    ));
     while let Ok((stream, address)) = listener.accept().await {
         tokio::spawn(accept_connection(game_server.clone(), stream, address));
     }
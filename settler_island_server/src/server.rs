@@ -1,13 +1,22 @@
 use std::{collections::HashMap, sync::Arc};
 
-use log::{debug, info, trace};
-use tokio::sync::Mutex;
+use log::{debug, error, info, trace};
+use serde_json::json;
+use tokio::{
+    sync::Mutex,
+    time::{self, Instant},
+};
 use uuid::Uuid;
 
 use crate::server::user::UserData;
 
 use self::{
-    lobby::lobby_browser::LobbyBrowser,
+    lobby::{
+        game_lobby::LOBBY_GROUP,
+        lobby_browser::{LobbyBrowser, RESTORED_LOBBY_GRACE_PERIOD},
+    },
+    message::error_codes::{self, ErrorCode},
+    session::{Session, SessionToken, SESSION_GRACE_PERIOD},
     user::{ServerUser, UserId},
     user_connection::UserConnection,
 };
@@ -15,6 +24,7 @@ use self::{
 pub mod error;
 pub mod lobby;
 pub mod message;
+pub mod session;
 pub mod user;
 pub mod user_connection;
 
@@ -23,16 +33,41 @@ pub type GameServerAccess = Arc<Mutex<GameServer>>;
 pub struct GameServer {
     lobby_browser: LobbyBrowser,
     users: Mutex<HashMap<UserId, UserConnection>>,
+    sessions: HashMap<SessionToken, Session>,
 }
 
 impl GameServer {
     pub fn new() -> GameServerAccess {
+        GameServer::from(LobbyBrowser::new())
+    }
+
+    pub fn from(lobby_browser: LobbyBrowser) -> GameServerAccess {
         Arc::new(Mutex::new(GameServer {
-            lobby_browser: LobbyBrowser::new(),
+            lobby_browser,
             users: Mutex::new(HashMap::new()),
+            sessions: HashMap::new(),
         }))
     }
 
+    /// Restores the lobbies and games saved before the last shutdown
+    pub async fn restore_lobbies(&mut self) -> Result<(), String> {
+        self.lobby_browser.restore_lobbies().await
+    }
+
+    /// Closes the restored lobbies which no user joined again within the grace period
+    pub async fn close_abandoned_lobbies_after_grace_period(server: GameServerAccess) {
+        time::sleep(RESTORED_LOBBY_GRACE_PERIOD).await;
+        if let Err(err) = server
+            .lock()
+            .await
+            .lobby_browser
+            .close_empty_lobbies()
+            .await
+        {
+            error!("Failed to close abandoned lobbies \"{}\"", err);
+        }
+    }
+
     pub async fn register_user(
         &mut self,
         username: &str,
@@ -52,29 +87,164 @@ impl GameServer {
             return Err(format!("Failed to register user with {:?}", user));
         }
 
+        let session_token = Uuid::new_v4().as_simple().to_string();
+        self.sessions
+            .insert(session_token.clone(), Session::from(id.clone()));
+
         users.insert(id.to_string(), user_connection.clone());
         debug!("Registered {:?}", user);
-        user_connection.get_game_state().lock().await.user = Some(user);
+        let mut game_state = user_connection.get_game_state().lock().await;
+        game_state.user = Some(user);
+        game_state.session_token = Some(session_token);
+
+        Ok(())
+    }
+
+    /// Holds the seat of the user for the grace period and tells the lobby that the user is gone.
+    /// Returns the time of the disconnect which is needed to expire the session
+    /// or `None` if another connection resumed the session already
+    pub async fn disconnect_user(
+        &mut self,
+        session_token: &SessionToken,
+        user_connection: &UserConnection,
+    ) -> Result<Option<Instant>, String> {
+        let session = match self.sessions.get_mut(session_token) {
+            None => return Err(String::from("Session not found")),
+            Some(session) => session,
+        };
+        let user_id = session.get_user_id().clone();
+
+        match self.users.lock().await.get(&user_id) {
+            None => return Err(String::from("User not found")),
+            Some(session_connection) if !session_connection.is_same_connection(user_connection) => {
+                return Ok(None)
+            }
+            Some(_) => (),
+        }
+        let disconnected_at = Instant::now();
+        session.set_disconnected_at(Some(disconnected_at));
+
+        let lobby = user_connection.get_game_state().lock().await.lobby.clone();
+        if let Some(lobby) = lobby {
+            let lobby = lobby.lock().await;
+            let payloads = lobby
+                .get_users()
+                .keys()
+                .filter(|lobby_user_id| *lobby_user_id != &user_id)
+                .map(|lobby_user_id| (lobby_user_id.clone(), json!({ "user_id": user_id })))
+                .collect();
+            lobby
+                .broadcast_each(LOBBY_GROUP, "user-disconnected", payloads)
+                .await;
+        }
+
+        debug!("User \"{}\" disconnected", user_id);
+        Ok(Some(disconnected_at))
+    }
+
+    /// Unregisters the user after the grace period if the session was not resumed in the meantime
+    pub async fn expire_session_after_grace_period(
+        server: GameServerAccess,
+        session_token: SessionToken,
+        disconnected_at: Instant,
+    ) {
+        time::sleep(SESSION_GRACE_PERIOD).await;
+        if let Err(err) = server
+            .lock()
+            .await
+            .expire_session(&session_token, disconnected_at)
+            .await
+        {
+            error!("Failed to expire session \"{}\"", err);
+        }
+    }
+
+    async fn expire_session(
+        &mut self,
+        session_token: &SessionToken,
+        disconnected_at: Instant,
+    ) -> Result<(), String> {
+        let user_id = match self.sessions.get(session_token) {
+            None => return Ok(()),
+            Some(session) if session.get_disconnected_at() != &Some(disconnected_at) => {
+                return Ok(())
+            }
+            Some(session) => session.get_user_id().clone(),
+        };
+
+        self.sessions.remove(session_token);
+        debug!("Session of user \"{}\" expired", user_id);
+        self.unregister_user(&user_id).await
+    }
+
+    /// Attaches the connection to the user of the session and replaces the user's old connection
+    pub async fn resume_session(
+        &mut self,
+        session_token: &SessionToken,
+        user_connection: &UserConnection,
+    ) -> Result<(), ErrorCode> {
+        let session = match self.sessions.get_mut(session_token) {
+            None => return Err(error_codes::INVALID_SESSION),
+            Some(session) => session,
+        };
+        let user_id = session.get_user_id().clone();
+
+        let mut users = self.users.lock().await;
+        let old_user_connection = match users.get(&user_id) {
+            None => return Err(error_codes::INVALID_SESSION),
+            Some(old_user_connection) => old_user_connection.clone(),
+        };
+
+        let (user, lobby) = {
+            // the old connection must not unregister the user once its socket closes
+            let mut old_game_state = old_user_connection.get_game_state().lock().await;
+            old_game_state.session_token = None;
+            (old_game_state.user.take(), old_game_state.lobby.take())
+        };
+        session.set_disconnected_at(None);
+
+        {
+            let mut game_state = user_connection.get_game_state().lock().await;
+            game_state.user = user;
+            game_state.lobby = lobby.clone();
+            game_state.session_token = Some(session_token.clone());
+        }
+        users.insert(user_id.clone(), user_connection.clone());
+
+        if let Some(lobby) = lobby {
+            let mut lobby = lobby.lock().await;
+            lobby.replace_user_connection(&user_id, user_connection.clone());
+            lobby
+                .broadcast(
+                    LOBBY_GROUP,
+                    "user-reconnected",
+                    json!({ "user_id": user_id }),
+                )
+                .await;
+        }
 
+        debug!("User \"{}\" resumed the session", user_id);
         Ok(())
     }
 
     pub async fn unregister_user(&mut self, id: &UserId) -> Result<(), String> {
         debug!("Unregister {} {}", id, self.users.try_lock().is_ok());
         trace!("Lock users");
-        let users = self.users.lock().await;
-        let user_connection = match users.get(id) {
+        let mut users = self.users.lock().await;
+        let user_connection = match users.remove(id) {
             None => return Err(String::from("User not found")),
             Some(user_connection) => user_connection,
         };
         trace!("Lock game_state");
         let mut game_state = user_connection.get_game_state().lock().await;
 
-        game_state
-            .leave_lobby(&mut self.lobby_browser)
-            .await
-            .unwrap();
+        if game_state.lobby.is_some() {
+            if let Err(err) = game_state.leave_lobby(&mut self.lobby_browser).await {
+                return Err(format!("Failed to leave lobby \"{}\"", err.0));
+            }
+        }
         game_state.user = None;
+        game_state.session_token = None;
         Ok(())
     }
 }
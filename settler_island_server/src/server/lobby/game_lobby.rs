@@ -1,28 +1,44 @@
-use std::{collections::HashMap, fmt::format, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::format,
+    sync::Arc,
+};
 
-use log::trace;
-use serde::Serialize;
+use futures_util::future::join_all;
+use log::{debug, error, trace};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use settler_island_game::game::{
-    board::generator::base_board_generator::generate_board,
-    state::{
-        state_machine::{GameStateT, StateMachine},
-        states::{
-            start_road_placement::StartRoadPlacement,
-            start_village_placement::StartVillagePlacement,
-        },
-    },
-    Game, GameError, GameSettings,
+    color::Color,
+    event::GameEvent,
+    player::{Player, PlayerId},
+    save::SavedGame,
+    snapshot::GameSnapshot,
+    state::{action_data::GameActionKind, legal_action::LegalAction},
+    Game, GameError, GameSettings, MAX_PLAYER_COUNT, MIN_PLAYER_COUNT,
 };
 use tokio::sync::Mutex;
+use uuid::Uuid;
 
 use crate::server::{
     error::ServerError,
-    user::{self, UserId},
+    message::error_codes::{self, ErrorCode},
+    user::{self, UserData, UserId},
     user_connection::UserConnection,
 };
 
+use super::lobby_storage::{LobbyStorage, LoggedAction};
+
 pub type GameLobbyAccess = Arc<Mutex<GameLobby>>;
 
+pub const LOBBY_GROUP: &str = "lobby";
+pub const GAME_GROUP: &str = "game";
+
+/// Secret of a seat which lets a user take over the player of the seat,
+/// e.g. after a server restart assigned the user a new id
+pub type SeatToken = String;
+
 pub struct GameLobby {
     id: String,
     name: String,
@@ -31,6 +47,41 @@ pub struct GameLobby {
     owner_id: UserId,
     users: HashMap<UserId, UserConnection>,
     game: Mutex<Option<Game>>,
+    /// Seat token of every player, indexed by player id
+    seat_tokens: Vec<SeatToken>,
+    storage: LobbyStorage,
+}
+
+/// Lobby and game state which is restored on server start.
+/// Connected users are not saved, they join again and claim their seat with its seat token
+#[derive(Serialize, Deserialize)]
+pub struct SavedLobby {
+    id: String,
+    name: String,
+    password: String,
+    player_count: u32,
+    owner_id: UserId,
+    game: Option<SavedGame>,
+    #[serde(default)]
+    seat_tokens: Vec<SeatToken>,
+    /// Actions of the action log which are performed on the restored game
+    #[serde(skip)]
+    logged_actions: Vec<LoggedAction>,
+}
+
+/// Sent only to the user of the seat
+#[derive(Serialize)]
+struct SeatMessage<'a> {
+    player_id: PlayerId,
+    seat_token: &'a SeatToken,
+}
+
+/// Broadcast after every action, the events are redacted for each recipient
+#[derive(Serialize)]
+struct ActionPerformedMessage<'a> {
+    player_id: PlayerId,
+    action: &'a GameActionKind,
+    events: Vec<GameEvent>,
 }
 
 #[derive(Serialize, Debug)]
@@ -49,6 +100,7 @@ impl GameLobby {
         player_count: u32,
         creator: UserConnection,
         owner_id: UserId,
+        storage: LobbyStorage,
     ) -> Result<Self, ServerError> {
         let mut users = HashMap::new();
         users.insert(owner_id.clone(), creator);
@@ -61,9 +113,75 @@ impl GameLobby {
             owner_id: owner_id,
             users: users,
             game: Mutex::new(None),
+            seat_tokens: vec![],
+            storage,
+        })
+    }
+
+    pub fn from_saved(saved_lobby: SavedLobby, storage: LobbyStorage) -> Result<Self, String> {
+        let game = match saved_lobby.game {
+            None => None,
+            Some(saved_game) => match saved_game.restore().and_then(|mut game| {
+                perform_logged_actions(&mut game, saved_lobby.logged_actions)?;
+                Ok(game)
+            }) {
+                Ok(game) => Some(game),
+                Err(err) => {
+                    return Err(format!(
+                        "Failed to restore game of lobby \"{}\" \"{}\"",
+                        saved_lobby.id, err
+                    ))
+                }
+            },
+        };
+
+        Ok(GameLobby {
+            id: saved_lobby.id,
+            name: saved_lobby.name,
+            password: saved_lobby.password,
+            player_count: saved_lobby.player_count,
+            owner_id: saved_lobby.owner_id,
+            users: HashMap::new(),
+            game: Mutex::new(game),
+            seat_tokens: saved_lobby.seat_tokens,
+            storage,
         })
     }
 
+    fn get_saved_lobby(&self, game: Option<&Game>) -> SavedLobby {
+        SavedLobby {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            password: self.password.clone(),
+            player_count: self.player_count,
+            owner_id: self.owner_id.clone(),
+            game: game.map(SavedGame::from),
+            seat_tokens: self.seat_tokens.clone(),
+            logged_actions: vec![],
+        }
+    }
+
+    /// Writes the lobby and its game to the lobby storage and clears the action log.
+    /// The game stays locked until the save is written so no action is logged in between
+    pub async fn save(&self) -> Result<(), String> {
+        let game = self.game.lock().await;
+        let saved_lobby = self.get_saved_lobby(game.as_ref());
+        self.storage.save_lobby(&saved_lobby).await
+    }
+
+    /// Appends the actions to the action log, failures are only logged
+    async fn log_actions(&self, logged_actions: Vec<LoggedAction>) {
+        if logged_actions.is_empty() {
+            return;
+        }
+        if let Err(err) = self.storage.append_actions(&self.id, &logged_actions).await {
+            error!(
+                "Failed to save actions of lobby \"{}\" \"{}\"",
+                self.id, err
+            );
+        }
+    }
+
     pub fn get_id(&self) -> &String {
         return &self.id;
     }
@@ -89,30 +207,35 @@ impl GameLobby {
     }
 
     pub async fn add_user(&mut self, user_connection: UserConnection) -> Result<(), String> {
-        let user_id = user_connection
+        let user = user_connection
             .get_game_state()
             .lock()
             .await
             .user
             .as_ref()
             .unwrap()
-            .get_id()
             .clone();
 
-        if (self.users.contains_key(&user_id)) {
-            return Err(format!("\"{}\" is already in lobby", user_id));
+        if (self.users.contains_key(user.get_id())) {
+            return Err(format!("\"{}\" is already in lobby", user.get_id()));
         }
 
-        self.users.insert(user_id, user_connection);
+        self.users.insert(user.get_id().clone(), user_connection);
+        match serde_json::to_value(&user) {
+            Ok(user_value) => self.broadcast(LOBBY_GROUP, "user-joined", user_value).await,
+            Err(err) => error!("Failed to serialize {:?} \"{}\"", user, err),
+        }
         Ok(())
     }
 
-    pub fn remove_user(&mut self, user_id: &UserId) -> Result<(), String> {
+    pub async fn remove_user(&mut self, user_id: &UserId) -> Result<(), String> {
         if !self.users.contains_key(user_id) {
             return Err(format!("\"{}\" is not in lobby", user_id));
         }
         self.users.remove(user_id).unwrap();
         trace!("User \"{}\" removed from lobby \"{}\"", user_id, self.id);
+        self.broadcast(LOBBY_GROUP, "user-left", json!({ "user_id": user_id }))
+            .await;
         if self.users.len() == 0 || user_id != &self.owner_id {
             return Ok(());
         }
@@ -124,37 +247,402 @@ impl GameLobby {
             self.id,
             self.owner_id
         );
+        self.broadcast(
+            LOBBY_GROUP,
+            "owner-changed",
+            json!({ "owner_id": self.owner_id }),
+        )
+        .await;
 
         Ok(())
     }
 
+    /// Sends the same message to every user in the lobby
+    pub async fn broadcast(&self, group: &str, command: &str, payload: Value) {
+        let payloads = self
+            .users
+            .keys()
+            .map(|user_id| (user_id.clone(), payload.clone()))
+            .collect();
+        self.broadcast_each(group, command, payloads).await
+    }
+
+    /// Sends a message with a payload shaped for each recipient,
+    /// users without a payload are skipped
+    pub async fn broadcast_each(
+        &self,
+        group: &str,
+        command: &str,
+        payloads: HashMap<UserId, Value>,
+    ) {
+        join_all(payloads.into_iter().map(|(user_id, payload)| async move {
+            let user_connection = match self.users.get(&user_id) {
+                None => return,
+                Some(user_connection) => user_connection,
+            };
+            if let Err(err) = user_connection.send(group, command, payload).await {
+                error!(
+                    "Failed to send {} {} to \"{}\" \"{}\"",
+                    group, command, user_id, err
+                );
+            }
+        }))
+        .await;
+    }
+
+    /// Game view of every user in the lobby
+    async fn get_user_snapshots(&self) -> HashMap<UserId, Value> {
+        let game = self.game.lock().await;
+        let game = match game.as_ref() {
+            None => return HashMap::new(),
+            Some(game) => game,
+        };
+
+        self.users
+            .keys()
+            .filter_map(|user_id| {
+                let snapshot = match get_user_player_id(game, user_id) {
+                    Ok(player_id) => GameSnapshot::for_player(game, &player_id),
+                    Err(_) => GameSnapshot::for_spectator(game),
+                };
+                match serde_json::to_value(snapshot) {
+                    Ok(snapshot_value) => Some((user_id.clone(), snapshot_value)),
+                    Err(err) => {
+                        error!("Failed to serialize snapshot \"{}\"", err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Swaps the connection of a user who resumed the session on a new socket
+    pub fn replace_user_connection(&mut self, user_id: &UserId, user_connection: UserConnection) {
+        if let Some(lobby_user_connection) = self.users.get_mut(user_id) {
+            *lobby_user_connection = user_connection;
+        }
+    }
+
     pub fn get_users(&self) -> &HashMap<UserId, UserConnection> {
         &self.users
     }
 
+    /// Player of the user and the actions the player can perform right now
+    pub async fn get_legal_actions(
+        &self,
+        user_id: &UserId,
+    ) -> Result<(PlayerId, Vec<LegalAction>), ErrorCode> {
+        let game = self.game.lock().await;
+        let game = match game.as_ref() {
+            None => return Err(error_codes::GAME_NOT_STARTED),
+            Some(game) => game,
+        };
+
+        let player_id = get_user_player_id(game, user_id)?;
+        Ok((player_id, game.get_legal_actions(&player_id)))
+    }
+
+    /// Game view of the user, users who are not playing get the spectator view
+    pub async fn get_snapshot(&self, user_id: &UserId) -> Result<GameSnapshot, ErrorCode> {
+        let game = self.game.lock().await;
+        let game = match game.as_ref() {
+            None => return Err(error_codes::GAME_NOT_STARTED),
+            Some(game) => game,
+        };
+
+        match get_user_player_id(game, user_id) {
+            Ok(player_id) => Ok(GameSnapshot::for_player(game, &player_id)),
+            Err(_) => Ok(GameSnapshot::for_spectator(game)),
+        }
+    }
+
+    /// Performs the action as the player of the user, logs it if the game recorded it
+    /// and broadcasts the resulting events if the action succeeded
+    pub async fn perform_action(
+        &self,
+        user_id: &UserId,
+        action: GameActionKind,
+    ) -> Result<PlayerId, ErrorCode> {
+        let (player_id, action_result, payloads) = {
+            let mut game = self.game.lock().await;
+            let game = match game.as_mut() {
+                None => return Err(error_codes::GAME_NOT_STARTED),
+                Some(game) => game,
+            };
+
+            let player_id = get_user_player_id(game, user_id)?;
+            trace!(
+                "Player {} of \"{}\" performs {}",
+                player_id,
+                user_id,
+                action.get_id()
+            );
+            let event_count = game.get_events().len();
+            let step = game.get_recorded_actions().len();
+            let action_result = game.perform_action(&player_id, action.clone());
+            self.log_actions(get_logged_actions(game, step)).await;
+            let payloads = match action_result {
+                Err(_) => HashMap::new(),
+                Ok(_) => self.get_action_payloads(game, &player_id, &action, event_count),
+            };
+            (player_id, action_result, payloads)
+        };
+
+        if let Err(game_error) = action_result {
+            return Err(error_codes::from_game_error(&game_error));
+        }
+
+        self.broadcast_each(GAME_GROUP, "action-performed", payloads)
+            .await;
+        Ok(player_id)
+    }
+
+    fn get_action_payloads(
+        &self,
+        game: &Game,
+        player_id: &PlayerId,
+        action: &GameActionKind,
+        event_count: usize,
+    ) -> HashMap<UserId, Value> {
+        let events = game.get_events_since(event_count);
+        self.users
+            .keys()
+            .filter_map(|user_id| {
+                let viewer_id = get_user_player_id(game, user_id).ok();
+                let message = ActionPerformedMessage {
+                    player_id: *player_id,
+                    action,
+                    events: events
+                        .iter()
+                        .map(|event| event.for_viewer(viewer_id.as_ref()))
+                        .collect(),
+                };
+                match serde_json::to_value(message) {
+                    Ok(message_value) => Some((user_id.clone(), message_value)),
+                    Err(err) => {
+                        error!("Failed to serialize action performed message \"{}\"", err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_owner_id(&self) -> &UserId {
+        &self.owner_id
+    }
+
+    /// Starts a game with a player for every joined user.
+    /// Players are seated in `seat_order` or in a shuffled order if no order is given,
+    /// every user receives the seat token of the own seat
     pub async fn start_game(
         &mut self,
-        settings: GameSettings,
-        player_user_ids: Vec<UserId>,
-    ) -> Result<(), String> {
-        let board = match generate_board() {
-            Ok(board) => board,
-            Err(err) => return Err(format!("Failed to generated board \"{}\"", err)),
+        user_id: &UserId,
+        mut settings: GameSettings,
+        seat_order: Option<Vec<UserId>>,
+    ) -> Result<(), ErrorCode> {
+        if user_id != &self.owner_id {
+            return Err(error_codes::NOT_LOBBY_OWNER);
+        }
+        if self.game.lock().await.is_some() {
+            return Err(error_codes::GAME_ALREADY_STARTED);
+        }
+
+        let max_player_count = (self.player_count as usize).min(MAX_PLAYER_COUNT);
+        if self.users.len() < MIN_PLAYER_COUNT || self.users.len() > max_player_count {
+            return Err(error_codes::INVALID_PLAYER_COUNT);
+        }
+
+        let seat_order = match seat_order {
+            None => {
+                let mut user_ids: Vec<UserId> = self.users.keys().cloned().collect();
+                user_ids.shuffle(&mut rand::thread_rng());
+                user_ids
+            }
+            Some(seat_order) => {
+                // every joined user is seated exactly once
+                let seated_user_ids: HashSet<&UserId> = seat_order.iter().collect();
+                if seat_order.len() != self.users.len()
+                    || seated_user_ids.len() != seat_order.len()
+                    || seat_order
+                        .iter()
+                        .any(|seat_user_id| !self.users.contains_key(seat_user_id))
+                {
+                    return Err(error_codes::INVALID_SEAT_ORDER);
+                }
+                seat_order
+            }
         };
-        let states: Vec<GameStateT> = vec![
-            Box::new(StartVillagePlacement::new()),
-            Box::new(StartRoadPlacement::new()),
-        ];
-        let start_state_id = states[0].get_id().to_string();
 
-        let state_machine = StateMachine::from(states, start_state_id);
+        let mut players = vec![];
+        for (seat, seat_user_id) in seat_order.iter().enumerate() {
+            let color = match Color::from_seat(seat) {
+                None => return Err(error_codes::INVALID_PLAYER_COUNT),
+                Some(color) => color,
+            };
+            let mut player = Player::from(seat, color, settings.pieces);
+            player.set_user_id(Some(seat_user_id.clone()));
+            players.push(player);
+        }
 
-        self.game
-            .lock()
-            .await
-            .replace(Game::from(board, settings, state_machine));
+        settings.players = players.len() as u8;
+        let game = match Game::generate(settings, players) {
+            Ok(game) => game,
+            Err(err) => {
+                error!("Failed to generate game \"{}\"", err);
+                return Err(error_codes::LOBBY_INTERNAL_ERROR);
+            }
+        };
+        debug!(
+            "Lobby \"{}\" started a game with {} players",
+            self.id,
+            self.users.len()
+        );
+
+        self.game.lock().await.replace(game);
+        self.seat_tokens = seat_order
+            .iter()
+            .map(|_| Uuid::new_v4().as_simple().to_string())
+            .collect();
+        if let Err(err) = self.save().await {
+            error!("Failed to save lobby \"{}\" \"{}\"", self.id, err);
+        }
+
+        let snapshots = self.get_user_snapshots().await;
+        self.broadcast_each(LOBBY_GROUP, "game-started", snapshots)
+            .await;
+        let seat_messages = seat_order
+            .into_iter()
+            .zip(self.seat_tokens.iter())
+            .enumerate()
+            .filter_map(|(player_id, (seat_user_id, seat_token))| {
+                match serde_json::to_value(SeatMessage {
+                    player_id,
+                    seat_token,
+                }) {
+                    Ok(seat_value) => Some((seat_user_id, seat_value)),
+                    Err(err) => {
+                        error!("Failed to serialize seat message \"{}\"", err);
+                        None
+                    }
+                }
+            })
+            .collect();
+        self.broadcast_each(LOBBY_GROUP, "seat", seat_messages)
+            .await;
         Ok(())
     }
+
+    /// Assigns the player of the seat token to the user, the previous user loses the seat
+    pub async fn claim_seat(
+        &self,
+        user_id: &UserId,
+        seat_token: &SeatToken,
+    ) -> Result<PlayerId, ErrorCode> {
+        let player_id = {
+            let game = self.game.lock().await;
+            let game = match game.as_ref() {
+                None => return Err(error_codes::GAME_NOT_STARTED),
+                Some(game) => game,
+            };
+            if get_user_player_id(game, user_id).is_ok() {
+                return Err(error_codes::ALREADY_A_PLAYER);
+            }
+
+            let player_id = match self
+                .seat_tokens
+                .iter()
+                .position(|player_seat_token| player_seat_token == seat_token)
+            {
+                None => return Err(error_codes::INVALID_SEAT_TOKEN),
+                Some(player_id) => player_id,
+            };
+            match game.get_players().get(player_id) {
+                None => return Err(error_codes::INVALID_SEAT_TOKEN),
+                Some(player) => player.borrow_mut().set_user_id(Some(user_id.clone())),
+            }
+            player_id
+        };
+        debug!(
+            "User \"{}\" claimed seat {} in lobby \"{}\"",
+            user_id, player_id, self.id
+        );
+
+        if let Err(err) = self.save().await {
+            error!("Failed to save lobby \"{}\" \"{}\"", self.id, err);
+        }
+        self.broadcast(
+            LOBBY_GROUP,
+            "seat-claimed",
+            json!({ "player_id": player_id, "user_id": user_id }),
+        )
+        .await;
+        Ok(player_id)
+    }
+}
+
+/// Actions the game recorded since `step`
+fn get_logged_actions(game: &Game, step: usize) -> Vec<LoggedAction> {
+    game.get_recorded_actions()[step..]
+        .iter()
+        .enumerate()
+        .map(|(index, recorded_action)| LoggedAction {
+            step: step + index,
+            recorded_action: recorded_action.clone(),
+        })
+        .collect()
+}
+
+/// Performs the actions logged after the save, each has to end with its recorded result.
+/// Actions which are already part of the saved game are skipped
+fn perform_logged_actions(
+    game: &mut Game,
+    logged_actions: Vec<LoggedAction>,
+) -> Result<(), String> {
+    for logged_action in logged_actions {
+        let step = game.get_recorded_actions().len();
+        if logged_action.step < step {
+            continue;
+        }
+        if logged_action.step > step {
+            return Err(format!("Logged action {} is missing", step));
+        }
+
+        let recorded_action = logged_action.recorded_action;
+        let action_error = game
+            .perform_action(&recorded_action.player_id, recorded_action.action)
+            .err();
+        if action_error != recorded_action.error {
+            return Err(format!(
+                "Logged action {} ended with {:?} instead of {:?}",
+                step, action_error, recorded_action.error
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn get_user_player_id(game: &Game, user_id: &UserId) -> Result<PlayerId, ErrorCode> {
+    match game
+        .get_players()
+        .iter()
+        .map(|player| player.borrow())
+        .find(|player| player.get_user_id().as_ref() == Some(user_id))
+    {
+        None => Err(error_codes::NOT_A_PLAYER),
+        Some(player) => Ok(*player.get_id()),
+    }
+}
+
+impl SavedLobby {
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn with_logged_actions(mut self, logged_actions: Vec<LoggedAction>) -> Self {
+        self.logged_actions = logged_actions;
+        self
+    }
 }
 
 impl GameLobbySummary {
@@ -167,3 +655,319 @@ impl GameLobbySummary {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use futures_util::StreamExt;
+    use serde_json::Value;
+    use settler_island_game::game::{
+        save::SavedGame,
+        state::{
+            action_data::{GameActionKind, PlaceSettlementData},
+            legal_action::LegalAction,
+        },
+        GameSettings,
+    };
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::{
+        accept_async, connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream,
+    };
+    use uuid::Uuid;
+
+    use crate::server::{
+        lobby::{
+            game_lobby::GameLobbyAccess, lobby_browser::LobbyBrowser, lobby_storage::LobbyStorage,
+        },
+        message::error_codes,
+        user::UserId,
+        user_connection::UserConnection,
+        GameServer, GameServerAccess,
+    };
+
+    type ClientStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    fn create_storage() -> LobbyStorage {
+        LobbyStorage::from(env::temp_dir().join(format!(
+            "settler_island_test_{}",
+            Uuid::new_v4().as_simple()
+        )))
+    }
+
+    fn remove_storage(storage: &LobbyStorage) {
+        let _ = fs::remove_dir_all(storage.get_directory());
+    }
+
+    /// Registers a user on a real socket, the client side receives everything sent to the user
+    async fn connect_user(
+        server: &GameServerAccess,
+        username: &str,
+    ) -> (UserConnection, ClientStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_address = listener.local_addr().unwrap();
+        let (client, (server_stream, client_address)) =
+            tokio::join!(connect_async(format!("ws://{}", server_address)), async {
+                let (stream, client_address) = listener.accept().await.unwrap();
+                (accept_async(stream).await.unwrap(), client_address)
+            });
+
+        let user_connection = UserConnection::from(server_stream, client_address, server.clone());
+        server
+            .lock()
+            .await
+            .register_user(username, &user_connection)
+            .await
+            .unwrap();
+        (user_connection, client.unwrap().0)
+    }
+
+    async fn get_user_id(user_connection: &UserConnection) -> UserId {
+        user_connection
+            .get_game_state()
+            .lock()
+            .await
+            .user
+            .as_ref()
+            .unwrap()
+            .get_id()
+            .clone()
+    }
+
+    async fn get_lobby(user_connection: &UserConnection) -> GameLobbyAccess {
+        user_connection
+            .get_game_state()
+            .lock()
+            .await
+            .lobby
+            .clone()
+            .unwrap()
+    }
+
+    /// Payload of the next message with the command
+    async fn receive_payload(client: &mut ClientStream, command: &str) -> Value {
+        loop {
+            let message = match client.next().await.unwrap().unwrap() {
+                Message::Text(message) => serde_json::from_str::<Value>(&message).unwrap(),
+                _ => continue,
+            };
+            if message["command"] == command {
+                return message["payload"].clone();
+            }
+        }
+    }
+
+    fn get_settlement_action(legal_actions: &[LegalAction]) -> GameActionKind {
+        match &legal_actions[0] {
+            LegalAction::BuildSettlement {
+                settlement_type,
+                settlement_ids,
+            } => GameActionKind::BuildSettlement(PlaceSettlementData {
+                settlement_type: *settlement_type,
+                settlement_id: settlement_ids[0].clone(),
+            }),
+            legal_action => panic!("Unexpected legal action {:?}", legal_action),
+        }
+    }
+
+    async fn restart_server(storage: &LobbyStorage) -> GameServerAccess {
+        let server = GameServer::from(LobbyBrowser::from(storage.clone()));
+        server.lock().await.restore_lobbies().await.unwrap();
+        server
+    }
+
+    /// Starts a two player game and returns the lobby id and the seat token of the owner
+    async fn start_saved_game(storage: &LobbyStorage) -> (String, String) {
+        let server = GameServer::from(LobbyBrowser::from(storage.clone()));
+        let (owner, mut owner_client) = connect_user(&server, "owner").await;
+        let (guest, _guest_client) = connect_user(&server, "guest").await;
+        server
+            .lock()
+            .await
+            .lobby_browser
+            .create_and_own_lobby(&owner, "lobby".to_string(), String::new(), 4)
+            .await
+            .unwrap();
+        let lobby = get_lobby(&owner).await;
+        let lobby_id = lobby.lock().await.get_id().clone();
+        server
+            .lock()
+            .await
+            .lobby_browser
+            .join_lobby(&guest, &lobby_id, &String::new())
+            .await
+            .unwrap();
+
+        let owner_id = get_user_id(&owner).await;
+        let guest_id = get_user_id(&guest).await;
+        lobby
+            .lock()
+            .await
+            .start_game(
+                &owner_id,
+                GameSettings::default(),
+                Some(vec![owner_id.clone(), guest_id]),
+            )
+            .await
+            .unwrap();
+
+        let seat = receive_payload(&mut owner_client, "seat").await;
+        assert_eq!(seat["player_id"], 0);
+        (lobby_id, seat["seat_token"].as_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn returning_user_claims_seat_after_restart() {
+        let storage = create_storage();
+        let (lobby_id, seat_token) = start_saved_game(&storage).await;
+
+        let server = restart_server(&storage).await;
+        let (user, _client) = connect_user(&server, "owner").await;
+        server
+            .lock()
+            .await
+            .lobby_browser
+            .join_lobby(&user, &lobby_id, &String::new())
+            .await
+            .unwrap();
+        let user_id = get_user_id(&user).await;
+        let lobby = get_lobby(&user).await;
+        let lobby = lobby.lock().await;
+        assert_eq!(
+            lobby.get_legal_actions(&user_id).await.unwrap_err(),
+            error_codes::NOT_A_PLAYER
+        );
+
+        assert_eq!(
+            lobby.claim_seat(&user_id, &"unknown".to_string()).await,
+            Err(error_codes::INVALID_SEAT_TOKEN)
+        );
+        assert_eq!(lobby.claim_seat(&user_id, &seat_token).await, Ok(0));
+        assert_eq!(
+            lobby.claim_seat(&user_id, &seat_token).await,
+            Err(error_codes::ALREADY_A_PLAYER)
+        );
+
+        let (player_id, legal_actions) = lobby.get_legal_actions(&user_id).await.unwrap();
+        let action = get_settlement_action(&legal_actions);
+        assert_eq!(lobby.perform_action(&user_id, action).await, Ok(player_id));
+        remove_storage(&storage);
+    }
+
+    #[tokio::test]
+    async fn actions_are_logged_without_rewriting_the_lobby() {
+        let storage = create_storage();
+        let (lobby_id, seat_token) = start_saved_game(&storage).await;
+        let server = restart_server(&storage).await;
+        let (user, _client) = connect_user(&server, "owner").await;
+        server
+            .lock()
+            .await
+            .lobby_browser
+            .join_lobby(&user, &lobby_id, &String::new())
+            .await
+            .unwrap();
+        let user_id = get_user_id(&user).await;
+        let lobby = get_lobby(&user).await;
+        let lobby = lobby.lock().await;
+        lobby.claim_seat(&user_id, &seat_token).await.unwrap();
+        let lobby_path = storage.get_directory().join(format!("{}.json", lobby_id));
+        let lobby_json = fs::read_to_string(&lobby_path).unwrap();
+
+        let (_, legal_actions) = lobby.get_legal_actions(&user_id).await.unwrap();
+        let action = get_settlement_action(&legal_actions);
+        lobby.perform_action(&user_id, action).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&lobby_path).unwrap(), lobby_json);
+        let action_log_path = storage
+            .get_directory()
+            .join(format!("{}.actions.jsonl", lobby_id));
+        assert_eq!(
+            fs::read_to_string(action_log_path).unwrap().lines().count(),
+            1
+        );
+
+        let server = restart_server(&storage).await;
+        let restored_lobby = server
+            .lock()
+            .await
+            .lobby_browser
+            .get_lobby_by_id(&lobby_id)
+            .unwrap();
+        let restored_lobby = restored_lobby.lock().await;
+        let restored_game = restored_lobby.game.lock().await;
+        let game = lobby.game.lock().await;
+        assert_eq!(
+            serde_json::to_string(&SavedGame::from(restored_game.as_ref().unwrap())).unwrap(),
+            serde_json::to_string(&SavedGame::from(game.as_ref().unwrap())).unwrap()
+        );
+        remove_storage(&storage);
+    }
+
+    #[tokio::test]
+    async fn abandoned_restored_lobbies_are_closed() {
+        let storage = create_storage();
+        let (lobby_id, _) = start_saved_game(&storage).await;
+
+        let server = restart_server(&storage).await;
+        let mut server = server.lock().await;
+        assert!(server.lobby_browser.get_lobby_by_id(&lobby_id).is_some());
+        server.lobby_browser.close_empty_lobbies().await.unwrap();
+
+        assert!(server.lobby_browser.get_lobby_by_id(&lobby_id).is_none());
+        assert!(storage.load_lobbies().await.unwrap().is_empty());
+        remove_storage(&storage);
+    }
+
+    #[tokio::test]
+    async fn start_game_rejects_invalid_seat_order() {
+        let storage = create_storage();
+        let server = GameServer::from(LobbyBrowser::from(storage.clone()));
+        let (owner, _owner_client) = connect_user(&server, "owner").await;
+        let (guest, _guest_client) = connect_user(&server, "guest").await;
+        server
+            .lock()
+            .await
+            .lobby_browser
+            .create_and_own_lobby(&owner, "lobby".to_string(), String::new(), 4)
+            .await
+            .unwrap();
+        let lobby = get_lobby(&owner).await;
+        let lobby_id = lobby.lock().await.get_id().clone();
+        server
+            .lock()
+            .await
+            .lobby_browser
+            .join_lobby(&guest, &lobby_id, &String::new())
+            .await
+            .unwrap();
+        let owner_id = get_user_id(&owner).await;
+        let guest_id = get_user_id(&guest).await;
+
+        let mut lobby = lobby.lock().await;
+        for seat_order in [
+            vec![owner_id.clone(), owner_id.clone()],
+            vec![owner_id.clone(), guest_id.clone(), guest_id.clone()],
+            vec![owner_id.clone()],
+            vec![owner_id.clone(), "unknown".to_string()],
+        ] {
+            assert_eq!(
+                lobby
+                    .start_game(&owner_id, GameSettings::default(), Some(seat_order))
+                    .await,
+                Err(error_codes::INVALID_SEAT_ORDER)
+            );
+        }
+        assert_eq!(
+            lobby
+                .start_game(
+                    &owner_id,
+                    GameSettings::default(),
+                    Some(vec![guest_id, owner_id.clone()])
+                )
+                .await,
+            Ok(())
+        );
+        remove_storage(&storage);
+    }
+}
@@ -1,6 +1,6 @@
-use std::{cmp, collections::HashMap};
+use std::{cmp, collections::HashMap, time::Duration};
 
-use log::{debug, logger, trace};
+use log::{debug, error, info, logger, trace};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
@@ -10,19 +10,49 @@ use crate::server::{
     user_connection::{self, UserConnection},
 };
 
-use super::game_lobby::{GameLobby, GameLobbyAccess};
+use super::{
+    game_lobby::{GameLobby, GameLobbyAccess},
+    lobby_storage::LobbyStorage,
+};
+
+/// Time a restored lobby waits for its users to join again before it is closed
+pub const RESTORED_LOBBY_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
 
 pub struct LobbyBrowser {
     lobbies: HashMap<String, GameLobbyAccess>,
+    storage: LobbyStorage,
 }
 
 impl LobbyBrowser {
     pub fn new() -> Self {
+        LobbyBrowser::from(LobbyStorage::from_env())
+    }
+
+    pub fn from(storage: LobbyStorage) -> Self {
         LobbyBrowser {
             lobbies: HashMap::new(),
+            storage,
         }
     }
 
+    /// Restores the saved lobbies, lobbies which cannot be restored are skipped
+    pub async fn restore_lobbies(&mut self) -> Result<(), String> {
+        let saved_lobbies = self.storage.load_lobbies().await?;
+        for saved_lobby in saved_lobbies {
+            let lobby_id = saved_lobby.get_id().clone();
+            match GameLobby::from_saved(saved_lobby, self.storage.clone()) {
+                Ok(lobby) => {
+                    self.lobbies
+                        .insert(lobby_id, GameLobbyAccess::new(Mutex::new(lobby)));
+                }
+                Err(err) => error!("{}", err),
+            }
+        }
+
+        info!("Restored {} lobbies", self.lobbies.len());
+        Ok(())
+    }
+
     pub async fn create_and_own_lobby(
         &mut self,
         owner: &UserConnection,
@@ -46,6 +76,7 @@ impl LobbyBrowser {
             player_count,
             owner.clone(),
             owner_game_state.user.as_ref().unwrap().get_id().clone(),
+            self.storage.clone(),
         )
         .await
         {
@@ -110,7 +141,7 @@ impl LobbyBrowser {
         trace!("Lock lobby");
         let mut lobby = lobby.lock().await;
 
-        if let Err(err) = lobby.remove_user(user_id) {
+        if let Err(err) = lobby.remove_user(user_id).await {
             return Err(error_codes::LOBBY_INTERNAL_ERROR);
         }
 
@@ -121,6 +152,9 @@ impl LobbyBrowser {
         }
 
         debug!("Lobby \"{}\" is closing", lobby.get_id());
+        if let Err(err) = self.storage.remove_lobby(lobby.get_id()).await {
+            error!("{}", err);
+        }
 
         match self.lobbies.remove(lobby.get_id()) {
             None => Err(error_codes::LOBBY_NOT_FOUND),
@@ -128,8 +162,24 @@ impl LobbyBrowser {
         }
     }
 
-    pub fn close_lobby(&mut self, lobby_id: &String) -> Result<(), String> {
+    /// Closes the lobbies without users, e.g. restored lobbies nobody joined again
+    pub async fn close_empty_lobbies(&mut self) -> Result<(), String> {
+        let mut empty_lobby_ids = vec![];
+        for (lobby_id, lobby) in self.lobbies.iter() {
+            if lobby.lock().await.get_joined_user_count() == 0 {
+                empty_lobby_ids.push(lobby_id.clone());
+            }
+        }
+
+        for lobby_id in empty_lobby_ids {
+            self.close_lobby(&lobby_id).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn close_lobby(&mut self, lobby_id: &String) -> Result<(), String> {
         trace!("Closing lobby \"{}\"", lobby_id);
+        self.storage.remove_lobby(lobby_id).await?;
         match self.lobbies.remove(lobby_id) {
             None => Err(String::from("Lobby not found")),
             Some(_) => Ok(()),
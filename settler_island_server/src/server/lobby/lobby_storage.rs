@@ -0,0 +1,226 @@
+use std::{env, io, path::PathBuf};
+
+use log::{debug, error, trace};
+use serde::{Deserialize, Serialize};
+use settler_island_game::game::replay::RecordedAction;
+use tokio::{fs, io::AsyncWriteExt};
+
+use super::game_lobby::SavedLobby;
+
+pub const SAVE_DIRECTORY_VARIABLE: &str = "SETTLER_ISLAND_SAVE_DIR";
+pub const DEFAULT_SAVE_DIRECTORY: &str = "saves";
+
+/// Action recorded after the last full save of a lobby
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoggedAction {
+    /// Index of the action in the recorded actions of the game
+    pub step: usize,
+    pub recorded_action: RecordedAction,
+}
+
+/// Persists lobbies and their games as JSON files, one file per lobby.
+/// Actions are appended to an action log per lobby as JSON Lines,
+/// so a single action does not rewrite the whole game
+#[derive(Debug, Clone)]
+pub struct LobbyStorage {
+    directory: PathBuf,
+}
+
+impl LobbyStorage {
+    pub fn from(directory: PathBuf) -> Self {
+        LobbyStorage { directory }
+    }
+
+    /// Uses the directory of `SETTLER_ISLAND_SAVE_DIR` or `saves` if the variable is not set
+    pub fn from_env() -> Self {
+        match env::var(SAVE_DIRECTORY_VARIABLE) {
+            Ok(directory) => LobbyStorage::from(PathBuf::from(directory)),
+            Err(_) => LobbyStorage::from(PathBuf::from(DEFAULT_SAVE_DIRECTORY)),
+        }
+    }
+
+    pub fn get_directory(&self) -> &PathBuf {
+        &self.directory
+    }
+
+    fn get_lobby_path(&self, lobby_id: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", lobby_id))
+    }
+
+    fn get_action_log_path(&self, lobby_id: &str) -> PathBuf {
+        self.directory.join(format!("{}.actions.jsonl", lobby_id))
+    }
+
+    async fn remove_action_log(&self, lobby_id: &str) -> Result<(), String> {
+        let action_log_path = self.get_action_log_path(lobby_id);
+        match fs::remove_file(&action_log_path).await {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(format!(
+                "Failed to remove {:?} \"{}\"",
+                action_log_path, err
+            )),
+        }
+    }
+
+    pub async fn save_lobby(&self, saved_lobby: &SavedLobby) -> Result<(), String> {
+        let lobby_json = match serde_json::to_string(saved_lobby) {
+            Ok(lobby_json) => lobby_json,
+            Err(err) => return Err(format!("Failed to serialize lobby \"{}\"", err)),
+        };
+
+        if let Err(err) = fs::create_dir_all(&self.directory).await {
+            return Err(format!(
+                "Failed to create save directory {:?} \"{}\"",
+                self.directory, err
+            ));
+        }
+
+        // write to a temporary file first so a crash cannot leave a truncated save
+        let lobby_path = self.get_lobby_path(saved_lobby.get_id());
+        let temporary_path = lobby_path.with_extension("json.tmp");
+        if let Err(err) = fs::write(&temporary_path, lobby_json).await {
+            return Err(format!("Failed to write {:?} \"{}\"", temporary_path, err));
+        }
+        if let Err(err) = fs::rename(&temporary_path, &lobby_path).await {
+            return Err(format!("Failed to write {:?} \"{}\"", lobby_path, err));
+        }
+
+        // the save contains every logged action, leftovers are skipped by their step when loading
+        self.remove_action_log(saved_lobby.get_id()).await?;
+        trace!("Saved lobby \"{}\"", saved_lobby.get_id());
+        Ok(())
+    }
+
+    /// Appends the actions to the action log of the lobby, the next full save clears the log
+    pub async fn append_actions(
+        &self,
+        lobby_id: &str,
+        logged_actions: &[LoggedAction],
+    ) -> Result<(), String> {
+        let mut action_lines = String::new();
+        for logged_action in logged_actions {
+            match serde_json::to_string(logged_action) {
+                Ok(action_line) => action_lines += &(action_line + "\n"),
+                Err(err) => return Err(format!("Failed to serialize action \"{}\"", err)),
+            }
+        }
+
+        let action_log_path = self.get_action_log_path(lobby_id);
+        let mut action_log = match fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&action_log_path)
+            .await
+        {
+            Ok(action_log) => action_log,
+            Err(err) => return Err(format!("Failed to open {:?} \"{}\"", action_log_path, err)),
+        };
+        if let Err(err) = action_log.write_all(action_lines.as_bytes()).await {
+            return Err(format!("Failed to write {:?} \"{}\"", action_log_path, err));
+        }
+        if let Err(err) = action_log.flush().await {
+            return Err(format!("Failed to write {:?} \"{}\"", action_log_path, err));
+        }
+
+        trace!(
+            "Logged {} action(s) of lobby \"{}\"",
+            logged_actions.len(),
+            lobby_id
+        );
+        Ok(())
+    }
+
+    /// Actions logged since the last full save, a line cut off by a crash ends the log
+    async fn load_action_log(&self, lobby_id: &str) -> Result<Vec<LoggedAction>, String> {
+        let action_log_path = self.get_action_log_path(lobby_id);
+        let action_lines = match fs::read_to_string(&action_log_path).await {
+            Ok(action_lines) => action_lines,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(format!("Failed to read {:?} \"{}\"", action_log_path, err)),
+        };
+
+        let mut logged_actions = vec![];
+        for action_line in action_lines.lines() {
+            match serde_json::from_str::<LoggedAction>(action_line) {
+                Ok(logged_action) => logged_actions.push(logged_action),
+                Err(err) => {
+                    error!(
+                        "Failed to read action {} of {:?} \"{}\"",
+                        logged_actions.len(),
+                        action_log_path,
+                        err
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(logged_actions)
+    }
+
+    pub async fn remove_lobby(&self, lobby_id: &str) -> Result<(), String> {
+        self.remove_action_log(lobby_id).await?;
+        let lobby_path = self.get_lobby_path(lobby_id);
+        match fs::remove_file(&lobby_path).await {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(format!("Failed to remove {:?} \"{}\"", lobby_path, err)),
+        }
+    }
+
+    /// Reads every saved lobby, files which cannot be read are skipped
+    pub async fn load_lobbies(&self) -> Result<Vec<SavedLobby>, String> {
+        let mut entries = match fs::read_dir(&self.directory).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => {
+                return Err(format!(
+                    "Failed to read save directory {:?} \"{}\"",
+                    self.directory, err
+                ))
+            }
+        };
+
+        let mut saved_lobbies = vec![];
+        loop {
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(err) => return Err(format!("Failed to read save directory \"{}\"", err)),
+            };
+
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+
+            let lobby_json = match fs::read_to_string(&path).await {
+                Ok(lobby_json) => lobby_json,
+                Err(err) => {
+                    error!("Failed to read {:?} \"{}\"", path, err);
+                    continue;
+                }
+            };
+            let saved_lobby = match serde_json::from_str::<SavedLobby>(&lobby_json) {
+                Ok(saved_lobby) => saved_lobby,
+                Err(err) => {
+                    error!("Failed to read lobby {:?} \"{}\"", path, err);
+                    continue;
+                }
+            };
+            match self.load_action_log(saved_lobby.get_id()).await {
+                Ok(logged_actions) => {
+                    saved_lobbies.push(saved_lobby.with_logged_actions(logged_actions))
+                }
+                Err(err) => error!("{}", err),
+            }
+        }
+
+        debug!(
+            "Loaded {} lobby save(s) from {:?}",
+            saved_lobbies.len(),
+            self.directory
+        );
+        Ok(saved_lobbies)
+    }
+}
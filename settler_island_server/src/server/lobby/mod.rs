@@ -1,3 +1,4 @@
 pub mod game_lobby;
 pub mod lobby_browser;
+pub mod lobby_storage;
 pub mod lobby_user;
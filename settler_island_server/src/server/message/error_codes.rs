@@ -1,8 +1,57 @@
+use settler_island_game::game::GameError;
+
 pub type ErrorCode = (&'static str, u32);
 
 pub static NOT_REGISTERED: ErrorCode = ("User is registered", 100);
 pub static INVALID_PASSWORD: ErrorCode = ("Invalid Password", 101);
+pub static INVALID_SESSION: ErrorCode = ("Session is invalid or expired", 102);
+pub static ALREADY_REGISTERED: ErrorCode = ("User is already registered", 103);
 pub static NOT_IN_LOBBY: ErrorCode = ("User did not join a lobby", 200);
 pub static LOBBY_NOT_FOUND: ErrorCode = ("Lobby not found", 201);
 pub static ALREADY_IN_LOBBY: ErrorCode = ("User is already in lobby", 202);
 pub static LOBBY_INTERNAL_ERROR: ErrorCode = ("Lobby internal error", 203);
+pub static NOT_LOBBY_OWNER: ErrorCode = ("User is not the lobby owner", 204);
+pub static INVALID_PLAYER_COUNT: ErrorCode = ("Joined user count is not a valid player count", 205);
+pub static INVALID_SEAT_ORDER: ErrorCode = ("Seat order does not match the joined users", 206);
+pub static GAME_NOT_STARTED: ErrorCode = ("Game has not started", 300);
+pub static NOT_A_PLAYER: ErrorCode = ("User is not a player of the game", 301);
+pub static GAME_ALREADY_STARTED: ErrorCode = ("Game has already started", 302);
+pub static INVALID_SEAT_TOKEN: ErrorCode = ("Seat token is invalid", 303);
+pub static ALREADY_A_PLAYER: ErrorCode = ("User is already a player of the game", 304);
+pub static ACTION_FAILED: ErrorCode = ("Action failed", 400);
+pub static ACTION_DATA_INVALID: ErrorCode = ("Action data is invalid", 401);
+pub static ACTION_NOT_ALLOWED: ErrorCode = ("Action is not allowed", 402);
+pub static NOT_PLAYER_TURN: ErrorCode = ("It is not the turn of the player", 403);
+pub static INVALID_LOCATION: ErrorCode = ("Invalid location", 404);
+pub static NOT_ENOUGH_RESOURCES: ErrorCode = ("Not enough resources", 405);
+pub static NO_DEVELOPMENT_CARDS_LEFT: ErrorCode = ("No development cards left", 406);
+pub static DEVELOPMENT_CARD_NOT_OWNED: ErrorCode = ("Development card is not owned", 407);
+pub static DEVELOPMENT_CARD_NOT_PLAYABLE: ErrorCode = ("Development card is not playable", 408);
+pub static DEVELOPMENT_CARD_BOUGHT_THIS_TURN: ErrorCode =
+    ("Development card was bought this turn", 409);
+pub static DEVELOPMENT_CARD_ALREADY_PLAYED_THIS_TURN: ErrorCode =
+    ("A development card was already played this turn", 410);
+pub static NO_MATCHING_TRADE_CONTRACT: ErrorCode = ("No matching trade contract", 411);
+pub static NOT_ENOUGH_BANK_RESOURCES: ErrorCode = ("Bank has not enough resources", 412);
+pub static NO_PIECES_LEFT: ErrorCode = ("No pieces left", 413);
+
+pub fn from_game_error(game_error: &GameError) -> ErrorCode {
+    match game_error {
+        GameError::ActionFailed => ACTION_FAILED,
+        GameError::ActionDataInvalid => ACTION_DATA_INVALID,
+        GameError::ActionNotAllowed => ACTION_NOT_ALLOWED,
+        GameError::NotPlayerTurn => NOT_PLAYER_TURN,
+        GameError::InvalidLocation => INVALID_LOCATION,
+        GameError::NotEnoughResources => NOT_ENOUGH_RESOURCES,
+        GameError::NoDevelopmentCardsLeft => NO_DEVELOPMENT_CARDS_LEFT,
+        GameError::DevelopmentCardNotOwned => DEVELOPMENT_CARD_NOT_OWNED,
+        GameError::DevelopmentCardNotPlayable => DEVELOPMENT_CARD_NOT_PLAYABLE,
+        GameError::DevelopmentCardBoughtThisTurn => DEVELOPMENT_CARD_BOUGHT_THIS_TURN,
+        GameError::DevelopmentCardAlreadyPlayedThisTurn => {
+            DEVELOPMENT_CARD_ALREADY_PLAYED_THIS_TURN
+        }
+        GameError::NoMatchingTradeContract => NO_MATCHING_TRADE_CONTRACT,
+        GameError::NotEnoughBankResources => NOT_ENOUGH_BANK_RESOURCES,
+        GameError::NoPiecesLeft => NO_PIECES_LEFT,
+    }
+}
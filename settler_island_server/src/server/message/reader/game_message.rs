@@ -0,0 +1,189 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use settler_island_game::game::{
+    player::PlayerId,
+    state::{action_data::GameActionKind, legal_action::LegalAction},
+};
+
+use crate::server::{
+    lobby::game_lobby::GameLobbyAccess, message::error_codes, user_connection::UserConnection,
+};
+
+use super::MessageReaderProvider;
+
+#[derive(Deserialize)]
+struct PerformActionMessage {
+    pub action: GameActionKind,
+}
+
+#[derive(Serialize)]
+struct LegalActionsMessage {
+    pub player_id: PlayerId,
+    pub actions: Vec<LegalAction>,
+}
+
+pub struct GameMessage {
+    group: String,
+}
+
+impl GameMessage {
+    pub fn new() -> Self {
+        GameMessage {
+            group: "game".to_string(),
+        }
+    }
+
+    async fn perform_action(
+        &self,
+        user_connection: &UserConnection,
+        lobby: &GameLobbyAccess,
+        message: &str,
+    ) -> Result<(), String> {
+        let user_id = match user_connection.get_game_state().lock().await.user.as_ref() {
+            None => return Err("User not registered".to_string()),
+            Some(user) => user.get_id().clone(),
+        };
+
+        let action = match serde_json::from_str::<PerformActionMessage>(message) {
+            Ok(request) => request.action,
+            Err(err) => {
+                let _ = user_connection
+                    .send_error_details(error_codes::ACTION_DATA_INVALID, &err.to_string())
+                    .await;
+                return Err(format!(
+                    "Failed to parse perform action request \"{}\"",
+                    err
+                ));
+            }
+        };
+
+        // the lobby broadcasts the performed action to every user including the sender
+        if let Err(error_code) = lobby
+            .lock()
+            .await
+            .perform_action(&user_id, action.clone())
+            .await
+        {
+            let _ = user_connection.send_error(error_code).await;
+            return Err(format!(
+                "Action {} of \"{}\" failed \"{}\"",
+                action.get_id(),
+                user_id,
+                error_code.0
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn get_state(
+        &self,
+        user_connection: &UserConnection,
+        lobby: &GameLobbyAccess,
+    ) -> Result<(), String> {
+        let user_id = match user_connection.get_game_state().lock().await.user.as_ref() {
+            None => return Err("User not registered".to_string()),
+            Some(user) => user.get_id().clone(),
+        };
+
+        let snapshot = match lobby.lock().await.get_snapshot(&user_id).await {
+            Err(error_code) => {
+                let _ = user_connection.send_error(error_code).await;
+                return Err(format!(
+                    "Failed to get game state of \"{}\" \"{}\"",
+                    user_id, error_code.0
+                ));
+            }
+            Ok(snapshot) => snapshot,
+        };
+
+        let snapshot_value = match serde_json::to_value(snapshot) {
+            Ok(json_value) => json_value,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        match user_connection
+            .send(&self.group, "state", snapshot_value)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Failed to send game state message \"{}\"", err)),
+        }
+    }
+
+    async fn get_legal_actions(
+        &self,
+        user_connection: &UserConnection,
+        lobby: &GameLobbyAccess,
+    ) -> Result<(), String> {
+        let user_id = match user_connection.get_game_state().lock().await.user.as_ref() {
+            None => return Err("User not registered".to_string()),
+            Some(user) => user.get_id().clone(),
+        };
+
+        let (player_id, actions) = match lobby.lock().await.get_legal_actions(&user_id).await {
+            Err(error_code) => {
+                let _ = user_connection.send_error(error_code).await;
+                return Err(format!(
+                    "Failed to get legal actions of \"{}\" \"{}\"",
+                    user_id, error_code.0
+                ));
+            }
+            Ok(legal_actions) => legal_actions,
+        };
+
+        let legal_actions_value =
+            match serde_json::to_value(LegalActionsMessage { player_id, actions }) {
+                Ok(json_value) => json_value,
+                Err(err) => return Err(err.to_string()),
+            };
+
+        match user_connection
+            .send(&self.group, "legal-actions", legal_actions_value)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Failed to send legal actions message \"{}\"", err)),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageReaderProvider for GameMessage {
+    fn get_group(&self) -> &String {
+        &self.group
+    }
+
+    async fn call(
+        &self,
+        user_connection: &UserConnection,
+        command: &str,
+        json_message: &str,
+    ) -> Result<(), String> {
+        if user_connection.get_game_state().lock().await.user.is_none() {
+            let _ = user_connection
+                .send_error(error_codes::NOT_REGISTERED)
+                .await;
+            return Err("User not registered".to_string());
+        }
+
+        let lobby = match user_connection.get_game_state().lock().await.lobby.as_ref() {
+            None => {
+                let _ = user_connection.send_error(error_codes::NOT_IN_LOBBY).await;
+                return Err("User not in lobby".to_string());
+            }
+            Some(lobby) => lobby.clone(),
+        };
+
+        match command {
+            "perform-action" => {
+                self.perform_action(user_connection, &lobby, json_message)
+                    .await
+            }
+            "get-state" => self.get_state(user_connection, &lobby).await,
+            "get-legal-actions" => self.get_legal_actions(user_connection, &lobby).await,
+            _ => Err(format!("Command \"{}\" not found", command)),
+        }
+    }
+}
@@ -6,7 +6,7 @@ use serde_json::{json, Error};
 use settler_island_game::game;
 
 use crate::server::{
-    lobby::game_lobby::{GameLobbyAccess, GameLobbySummary},
+    lobby::game_lobby::{GameLobbyAccess, GameLobbySummary, GAME_GROUP},
     message::error_codes,
     user::UserData,
     user_connection::UserConnection,
@@ -19,6 +19,11 @@ struct RegisterUserMessage {
     pub username: String,
 }
 
+#[derive(Deserialize)]
+struct ResumeMessage {
+    pub session_token: String,
+}
+
 #[derive(Deserialize)]
 struct GetLobbiesMessage {
     pub page: u32,
@@ -76,7 +81,62 @@ impl GameServerMessage {
             return Err(err);
         }
 
-        self.send_user(user_connection).await
+        self.send_user(user_connection).await?;
+        self.send_session(user_connection).await
+    }
+
+    /// Attaches this socket to a registered user and sends the user, the lobby and the game view
+    async fn resume(&self, user_connection: &UserConnection, message: &str) -> Result<(), String> {
+        let resume_message = match serde_json::from_str::<ResumeMessage>(message) {
+            Ok(request) => request,
+            Err(err) => return Err(format!("Failed to parse resume request \"{}\"", err)),
+        };
+
+        if user_connection.get_game_state().lock().await.user.is_some() {
+            let _ = user_connection
+                .send_error(error_codes::ALREADY_REGISTERED)
+                .await;
+            return Err("User is already registered".to_string());
+        }
+
+        if let Err(error_code) = user_connection
+            .get_server()
+            .lock()
+            .await
+            .resume_session(&resume_message.session_token, user_connection)
+            .await
+        {
+            let _ = user_connection.send_error(error_code).await;
+            return Err(format!("Failed to resume session \"{}\"", error_code.0));
+        }
+
+        self.send_user(user_connection).await?;
+        self.send_session(user_connection).await?;
+
+        let (user_id, lobby) = {
+            let game_state = user_connection.get_game_state().lock().await;
+            match (game_state.user.as_ref(), game_state.lobby.clone()) {
+                (Some(user), Some(lobby)) => (user.get_id().clone(), lobby),
+                _ => return Ok(()),
+            }
+        };
+        self.send_lobby(user_connection).await?;
+
+        let snapshot = match lobby.lock().await.get_snapshot(&user_id).await {
+            Err(_) => return Ok(()),
+            Ok(snapshot) => snapshot,
+        };
+        let snapshot_value = match serde_json::to_value(snapshot) {
+            Ok(json_value) => json_value,
+            Err(err) => return Err(err.to_string()),
+        };
+        match user_connection
+            .send(GAME_GROUP, "state", snapshot_value)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Failed to send game state message \"{}\"", err)),
+        }
     }
 
     async fn get_lobbies(
@@ -111,6 +171,31 @@ impl GameServerMessage {
         }
     }
 
+    async fn send_session(&self, user_connection: &UserConnection) -> Result<(), String> {
+        let session_token = match user_connection
+            .get_game_state()
+            .lock()
+            .await
+            .session_token
+            .clone()
+        {
+            Some(session_token) => session_token,
+            None => return Err("Session not found".to_string()),
+        };
+
+        match user_connection
+            .send(
+                self.get_group(),
+                "session",
+                json!({ "session_token": session_token }),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Failed to send session message \"{}\"", err)),
+        }
+    }
+
     async fn send_lobbies(
         &self,
         user_connection: &UserConnection,
@@ -223,7 +308,7 @@ impl GameServerMessage {
             .await
             .lobby_browser
             .create_and_own_lobby(
-                &user_connection,
+                user_connection,
                 creation_message.lobby_name,
                 creation_message.password,
                 4,
@@ -251,7 +336,8 @@ impl MessageReaderProvider for GameServerMessage {
     ) -> Result<(), String> {
         // unregistered
         match command {
-            "register" => return self.register_user(&user_connection, json_message).await,
+            "register" => return self.register_user(user_connection, json_message).await,
+            "resume" => return self.resume(user_connection, json_message).await,
             _ => (),
         }
 
@@ -264,11 +350,11 @@ impl MessageReaderProvider for GameServerMessage {
         }
 
         match command {
-            "get-lobbies" => return self.get_lobbies(&user_connection, json_message).await,
-            "join-lobby" => return self.join_lobby(&user_connection, json_message).await,
-            "get-user" => return self.send_user(&user_connection).await,
-            "get-lobby" => return self.send_lobby(&user_connection).await,
-            "create-lobby" => return self.create_game_lobby(&user_connection, json_message).await,
+            "get-lobbies" => return self.get_lobbies(user_connection, json_message).await,
+            "join-lobby" => return self.join_lobby(user_connection, json_message).await,
+            "get-user" => return self.send_user(user_connection).await,
+            "get-lobby" => return self.send_lobby(user_connection).await,
+            "create-lobby" => return self.create_game_lobby(user_connection, json_message).await,
             _ => (),
         }
 
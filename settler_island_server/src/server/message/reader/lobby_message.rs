@@ -3,12 +3,13 @@ use std::{collections::HashMap, fmt::format};
 use async_trait::async_trait;
 use futures_util::future::join_all;
 use log::trace;
+use serde::Deserialize;
 use serde_json::json;
 use settler_island_game::game::GameSettings;
 use tokio::sync::Mutex;
 
 use crate::server::{
-    lobby::game_lobby::GameLobbyAccess,
+    lobby::game_lobby::{GameLobbyAccess, SeatToken, GAME_GROUP},
     message::error_codes,
     user::{UserData, UserId},
     user_connection::{self, UserConnection},
@@ -18,6 +19,17 @@ use super::MessageReaderProvider;
 
 type UsersMessage = HashMap<UserId, UserData>;
 
+#[derive(Deserialize)]
+struct StartGameMessage {
+    #[serde(default)]
+    pub seat_order: Option<Vec<UserId>>,
+}
+
+#[derive(Deserialize)]
+struct ClaimSeatMessage {
+    pub seat_token: SeatToken,
+}
+
 pub struct LobbyMessage {
     group: String,
 }
@@ -44,8 +56,26 @@ impl LobbyMessage {
         }
     }
 
-    async fn start_game(&self, user_connection: &UserConnection) -> Result<(), String> {
+    async fn start_game(
+        &self,
+        user_connection: &UserConnection,
+        message: &str,
+    ) -> Result<(), String> {
+        let start_message = match serde_json::from_str::<StartGameMessage>(message) {
+            Ok(message) => message,
+            Err(err) => return Err(format!("Failed to parse start game message \"{}\"", err)),
+        };
+
         let game_state = user_connection.get_game_state().lock().await;
+        let user_id = match game_state.user.as_ref() {
+            Some(user) => user.get_id().clone(),
+            None => {
+                let _ = user_connection
+                    .send_error(error_codes::NOT_REGISTERED)
+                    .await;
+                return Err("User cannot start game as the user is not registered".to_string());
+            }
+        };
         let lobby = match game_state.lobby.as_ref() {
             Some(lobby) => lobby,
             None => {
@@ -54,18 +84,62 @@ impl LobbyMessage {
             }
         };
 
-        if let Err(err) = lobby
+        if let Err(error_code) = lobby
             .lock()
             .await
-            .start_game(GameSettings { players: 2 }, vec![])
+            .start_game(&user_id, GameSettings::default(), start_message.seat_order)
             .await
         {
-            return Err(format!("User could not start game \"{}\"", err));
+            let _ = user_connection.send_error(error_code).await;
+            return Err(format!("User could not start game \"{}\"", error_code.0));
         }
 
         Ok(())
     }
 
+    /// Takes over the player of the seat token and sends the game view of the player
+    async fn claim_seat(
+        &self,
+        user_connection: &UserConnection,
+        message: &str,
+    ) -> Result<(), String> {
+        let claim_message = match serde_json::from_str::<ClaimSeatMessage>(message) {
+            Ok(message) => message,
+            Err(err) => return Err(format!("Failed to parse claim seat message \"{}\"", err)),
+        };
+
+        let (user_id, lobby) = {
+            let game_state = user_connection.get_game_state().lock().await;
+            match (game_state.user.as_ref(), game_state.lobby.clone()) {
+                (Some(user), Some(lobby)) => (user.get_id().clone(), lobby),
+                _ => return Err("User cannot claim a seat outside of a lobby".to_string()),
+            }
+        };
+
+        let lobby = lobby.lock().await;
+        if let Err(error_code) = lobby.claim_seat(&user_id, &claim_message.seat_token).await {
+            let _ = user_connection.send_error(error_code).await;
+            return Err(format!("User could not claim seat \"{}\"", error_code.0));
+        }
+
+        let snapshot_value = match lobby.get_snapshot(&user_id).await {
+            Err(error_code) => {
+                return Err(format!("Failed to get game state \"{}\"", error_code.0))
+            }
+            Ok(snapshot) => match serde_json::to_value(snapshot) {
+                Ok(snapshot_value) => snapshot_value,
+                Err(err) => return Err(err.to_string()),
+            },
+        };
+        match user_connection
+            .send(GAME_GROUP, "state", snapshot_value)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("Failed to send game state message \"{}\"", err)),
+        }
+    }
+
     async fn get_users(&self, user_connection: &UserConnection) -> Result<(), String> {
         trace!("Process get-users");
         let lobby = match user_connection.get_game_state().lock().await.lobby.as_ref() {
@@ -155,9 +229,10 @@ impl MessageReaderProvider for LobbyMessage {
         }
 
         match command {
-            "leave" => return self.leave_lobby(&user_connection).await,
-            "start-game" => return self.start_game(&user_connection).await,
-            "get-users" => return self.get_users(&user_connection).await,
+            "leave" => return self.leave_lobby(user_connection).await,
+            "start-game" => return self.start_game(user_connection, json_message).await,
+            "claim-seat" => return self.claim_seat(user_connection, json_message).await,
+            "get-users" => return self.get_users(user_connection).await,
             _ => (),
         }
 
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::user::UserId;
+
+pub type SessionToken = String;
+
+/// Time a disconnected user keeps the lobby seat before the user is unregistered
+pub const SESSION_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Resumable registration of a user
+#[derive(Debug, Clone)]
+pub struct Session {
+    user_id: UserId,
+    disconnected_at: Option<Instant>,
+}
+
+impl Session {
+    pub fn from(user_id: UserId) -> Self {
+        Session {
+            user_id,
+            disconnected_at: None,
+        }
+    }
+
+    pub fn get_user_id(&self) -> &UserId {
+        &self.user_id
+    }
+
+    /// `None` while a socket is attached to the session
+    pub fn get_disconnected_at(&self) -> &Option<Instant> {
+        &self.disconnected_at
+    }
+
+    pub fn set_disconnected_at(&mut self, disconnected_at: Option<Instant>) {
+        self.disconnected_at = disconnected_at;
+    }
+}
@@ -16,7 +16,9 @@ use tokio_tungstenite::{
     WebSocketStream,
 };
 
-use crate::server::message::reader::{lobby_message::LobbyMessage, MessageBase};
+use crate::server::message::reader::{
+    game_message::GameMessage, lobby_message::LobbyMessage, MessageBase,
+};
 
 use super::{
     lobby::{
@@ -28,6 +30,7 @@ use super::{
         error_codes::{self, ErrorCode},
         reader::game_server_message::GameServerMessage,
     },
+    session::SessionToken,
     user::UserData,
     GameServer, GameServerAccess,
 };
@@ -39,6 +42,7 @@ pub type UserGameStateAccess = Arc<Mutex<UserGameState>>;
 pub struct UserGameState {
     pub user: Option<UserData>,
     pub lobby: Option<GameLobbyAccess>,
+    pub session_token: Option<SessionToken>,
 }
 
 #[derive(Clone)]
@@ -59,6 +63,7 @@ impl UserGameState {
         UserGameState {
             user: None,
             lobby: None,
+            session_token: None,
         }
     }
 
@@ -112,6 +117,9 @@ impl UserConnection {
         message_broker
             .register(Box::new(LobbyMessage::new()))
             .expect("Failed to register LobbyMessage");
+        message_broker
+            .register(Box::new(GameMessage::new()))
+            .expect("Failed to register GameMessage");
         let connection_address = user_connection.connection_address;
         while let Some(connection_message) =
             user_connection.stream_receive.lock().await.next().await
@@ -140,20 +148,36 @@ impl UserConnection {
 
         info!("Client {} disconnected", connection_address);
 
-        let opt_user = user_connection.game_state.lock().await.user.clone();
-        if let Some(user) = opt_user {
+        // the user keeps the seat until the session expires or another socket resumes it
+        let opt_session_token = user_connection
+            .game_state
+            .lock()
+            .await
+            .session_token
+            .clone();
+        if let Some(session_token) = opt_session_token {
             match user_connection
                 .server
                 .lock()
                 .await
-                .unregister_user(user.get_id())
+                .disconnect_user(&session_token, user_connection)
                 .await
             {
-                Ok(_) => {}
-                Err(err) => error!("Failed to unregister {:?} \"{}\"", user, err),
+                Ok(None) => (),
+                Ok(Some(disconnected_at)) => {
+                    tokio::spawn(GameServer::expire_session_after_grace_period(
+                        user_connection.server.clone(),
+                        session_token,
+                        disconnected_at,
+                    ));
+                }
+                Err(err) => error!("Failed to disconnect session \"{}\"", err),
             }
         }
-        info!("Client {} unregistered", connection_address);
+        info!(
+            "Client {} disconnected from its session",
+            connection_address
+        );
         Ok(())
     }
 
@@ -205,6 +229,16 @@ impl UserConnection {
         .await
     }
 
+    /// Sends an error with a detailed description, e.g. why a message could not be parsed
+    pub async fn send_error_details(&self, error: ErrorCode, details: &str) -> Result<()> {
+        self.send(
+            "error",
+            "error",
+            json!({"code": error.1, "message": error.0, "details": details}),
+        )
+        .await
+    }
+
     pub async fn send(&self, group: &str, command: &str, payload: Value) -> Result<()> {
         let message =
             json!({ "version": 1, "group": group, "command": command, "payload": payload });
@@ -218,6 +252,11 @@ impl UserConnection {
             .await
     }
 
+    /// Checks if both handles belong to the same socket
+    pub fn is_same_connection(&self, other: &UserConnection) -> bool {
+        Arc::ptr_eq(&self.game_state, &other.game_state)
+    }
+
     pub fn get_server(&self) -> &GameServerAccess {
         &self.server
     }